@@ -0,0 +1,507 @@
+//! Common alarm functions
+
+use super::super::{
+    Alarm, AlarmDateTime, AlarmMatching, BitFlags, DateTime, Error, Hours, Mcp794xx,
+    OutputPolarity, Register, Rtcc,
+};
+use super::datetime_math::{add_seconds, h24_to_hours, hours_to_h24, next_time_of_day};
+use super::{
+    check_between, check_lt, decimal_to_packed_bcd, hours_from_register, hours_to_register,
+    packed_bcd_to_decimal,
+};
+use interface;
+
+// Offsets of the alarm registers from the alarm seconds register
+struct AlarmOffset;
+impl AlarmOffset {
+    const SECONDS: u8 = 0;
+    const MINUTES: u8 = 1;
+    const HOURS: u8 = 2;
+    const WEEKDAY: u8 = 3;
+    const DAY: u8 = 4;
+    const MONTH: u8 = 5;
+}
+
+// Enable bit of an alarm in the CONTROL register
+fn alarm_enable_flag(alarm: Alarm) -> u8 {
+    match alarm {
+        Alarm::Zero => BitFlags::ALM0EN,
+        Alarm::One => BitFlags::ALM1EN,
+    }
+}
+
+// Address of the seconds register of an alarm
+fn alarm_base(alarm: Alarm) -> u8 {
+    match alarm {
+        Alarm::Zero => Register::ALM0SEC,
+        Alarm::One => Register::ALM1SEC,
+    }
+}
+
+impl<DI, IC, E> Mcp794xx<DI, IC>
+where
+    DI: interface::WriteData<Error = Error<E>> + interface::ReadData<Error = Error<E>>,
+{
+    /// Enable an alarm.
+    ///
+    /// The other bits of the CONTROL register are preserved.
+    pub fn enable_alarm(&mut self, alarm: Alarm) -> Result<(), Error<E>> {
+        self.modify_register(Register::CONTROL, alarm_enable_flag(alarm), true)
+    }
+
+    /// Disable an alarm.
+    ///
+    /// The other bits of the CONTROL register are preserved.
+    pub fn disable_alarm(&mut self, alarm: Alarm) -> Result<(), Error<E>> {
+        self.modify_register(Register::CONTROL, alarm_enable_flag(alarm), false)
+    }
+
+    /// Check whether an alarm has matched (ALMxIF).
+    pub fn has_alarm_matched(&mut self, alarm: Alarm) -> Result<bool, Error<E>> {
+        let data = self
+            .iface
+            .read_register(alarm_base(alarm) + AlarmOffset::WEEKDAY)?;
+        Ok((data & BitFlags::ALMIF) != 0)
+    }
+
+    /// Clear the alarm matched flag (ALMxIF).
+    ///
+    /// The MFP output stays asserted until this is done. The weekday,
+    /// matching configuration and output polarity bits sharing the register
+    /// are preserved.
+    pub fn clear_alarm_matched_flag(&mut self, alarm: Alarm) -> Result<(), Error<E>> {
+        self.modify_register(
+            alarm_base(alarm) + AlarmOffset::WEEKDAY,
+            BitFlags::ALMIF,
+            false,
+        )
+    }
+
+    /// Set the alarm 0 date and time and matching configuration.
+    ///
+    /// The alarm interrupt flag is cleared and the output polarity is kept.
+    /// This does not enable the alarm.
+    /// Returns `Error::InvalidInputData` if any of the fields is out of range.
+    pub fn set_alarm0(
+        &mut self,
+        datetime: &AlarmDateTime,
+        matching: AlarmMatching,
+    ) -> Result<(), Error<E>> {
+        self.set_alarm(Alarm::Zero, datetime, matching)
+    }
+
+    /// Read the alarm 0 date and time and matching configuration.
+    ///
+    /// Returns `Error::InvalidInputData` if the matching configuration holds
+    /// one of the reserved values.
+    pub fn get_alarm0(&mut self) -> Result<(AlarmDateTime, AlarmMatching), Error<E>> {
+        self.get_alarm(Alarm::Zero)
+    }
+
+    /// Set the alarm 1 date and time and matching configuration.
+    ///
+    /// The alarm interrupt flag is cleared. This does not enable the alarm.
+    /// Returns `Error::InvalidInputData` if any of the fields is out of range.
+    pub fn set_alarm1(
+        &mut self,
+        datetime: &AlarmDateTime,
+        matching: AlarmMatching,
+    ) -> Result<(), Error<E>> {
+        self.set_alarm(Alarm::One, datetime, matching)
+    }
+
+    /// Read the alarm 1 date and time and matching configuration.
+    ///
+    /// Returns `Error::InvalidInputData` if the matching configuration holds
+    /// one of the reserved values.
+    pub fn get_alarm1(&mut self) -> Result<(AlarmDateTime, AlarmMatching), Error<E>> {
+        self.get_alarm(Alarm::One)
+    }
+
+    /// Set an alarm date and time and matching configuration and enable it.
+    ///
+    /// The alarm registers are written in one burst, which also clears any
+    /// stale alarm interrupt flag, before the alarm is enabled in the
+    /// CONTROL register. The output polarity and the other bits of the
+    /// CONTROL register are kept.
+    /// Returns `Error::InvalidInputData` if any of the fields is out of
+    /// range, before anything is written.
+    pub fn setup_alarm(
+        &mut self,
+        alarm: Alarm,
+        datetime: &AlarmDateTime,
+        matching: AlarmMatching,
+    ) -> Result<(), Error<E>> {
+        self.set_alarm(alarm, datetime, matching)?;
+        self.enable_alarm(alarm)
+    }
+
+    /// Set alarm 0 to trigger once at the given date and time.
+    ///
+    /// The alarm is set with `AlarmMatching::AllMatch`, so the interrupt
+    /// only fires when the seconds, minutes, hour, weekday, day and month
+    /// all match. Make sure the weekday is consistent with the date,
+    /// otherwise the alarm never fires. The year is not stored.
+    /// The hour is converted to the 12h/24h format currently used by the
+    /// clock. The alarm interrupt flag is cleared and the output polarity
+    /// is kept. This does not enable the alarm.
+    /// Returns `Error::InvalidInputData` if any of the fields is out of range.
+    pub fn set_alarm0_all_fields(&mut self, datetime: &DateTime) -> Result<(), Error<E>> {
+        self.datetime_registers(datetime)?;
+        let clock_hours = hours_from_register(self.iface.read_register(Register::HOURS)?);
+        let datetime = DateTime {
+            hour: h24_to_hours(hours_to_h24(datetime.hour), clock_hours),
+            ..datetime.clone()
+        };
+        self.write_alarm0_all_match(&datetime)
+    }
+
+    /// Set alarm 1 to `offset_seconds` after alarm 0 as a backup.
+    ///
+    /// Alarm 0 is read back and alarm 1 is set to the resulting later date
+    /// and time with the same matching configuration, so that a missed
+    /// alarm 0 interrupt still triggers alarm 1. The alarm registers hold no
+    /// year, so the current year is used for the leap-year calculation.
+    /// The alarm 1 interrupt flag is cleared. This does not enable the alarm.
+    pub fn mirror_alarm0_to_alarm1_with_offset(
+        &mut self,
+        offset_seconds: u32,
+    ) -> Result<(), Error<E>> {
+        let (alarm0, matching) = self.get_alarm0()?;
+        let datetime = DateTime {
+            year: self.get_year()?,
+            month: alarm0.month,
+            day: alarm0.day,
+            weekday: alarm0.weekday,
+            hour: alarm0.hour,
+            minute: alarm0.minute,
+            second: alarm0.second,
+        };
+        let alarm1 = add_seconds(&datetime, offset_seconds);
+        let alarm1 = AlarmDateTime {
+            month: alarm1.month,
+            day: alarm1.day,
+            weekday: alarm1.weekday,
+            hour: alarm1.hour,
+            minute: alarm1.minute,
+            second: alarm1.second,
+        };
+        self.set_alarm(Alarm::One, &alarm1, matching)
+    }
+
+    /// Read the alarm 0 matching configuration.
+    ///
+    /// Only the ALM0WKDAY register is read. Returns
+    /// `Error::InvalidInputData` if it holds one of the reserved mask values.
+    pub fn get_alarm0_mask(&mut self) -> Result<AlarmMatching, Error<E>> {
+        let data = self.iface.read_register(Register::ALM0WKDAY)?;
+        alarm_matching_from_register(data)
+    }
+
+    /// Read the alarm output polarity.
+    ///
+    /// Only the ALMPOL bit of the ALM0WKDAY register is read. It applies to
+    /// both alarms.
+    pub fn get_alarm_polarity(&mut self) -> Result<OutputPolarity, Error<E>> {
+        let data = self.iface.read_register(Register::ALM0WKDAY)?;
+        if (data & BitFlags::ALMPOL) != 0 {
+            Ok(OutputPolarity::High)
+        } else {
+            Ok(OutputPolarity::Low)
+        }
+    }
+
+    /// Set the alarm output polarity.
+    ///
+    /// The polarity is global: the device has a single ALMPOL bit in the
+    /// ALM0WKDAY register (bit 7) and the same bit in ALM1WKDAY is not
+    /// implemented. Changing the polarity therefore affects the MFP output
+    /// of Alarm1 as well as Alarm0, which is why this method takes no
+    /// `Alarm` argument. Only the ALMPOL bit is changed. The weekday,
+    /// matching configuration and interrupt flag bits sharing the register
+    /// are preserved.
+    pub fn set_alarm_output_polarity(&mut self, polarity: OutputPolarity) -> Result<(), Error<E>> {
+        let high = polarity == OutputPolarity::High;
+        self.modify_register(Register::ALM0WKDAY, BitFlags::ALMPOL, high)
+    }
+
+    /// Check whether the alarm hours use the same 12h/24h format as the
+    /// clock hours.
+    ///
+    /// The alarm and time-keeping hours registers have independent format
+    /// bits. An alarm set in a different format than the clock will not
+    /// match as expected.
+    pub fn alarm_format_matches_clock(&mut self, alarm: Alarm) -> Result<bool, Error<E>> {
+        let clock = self.iface.read_register(Register::HOURS)?;
+        let alarm = self
+            .iface
+            .read_register(alarm_base(alarm) + AlarmOffset::HOURS)?;
+        Ok((clock & BitFlags::H24_H12) == (alarm & BitFlags::H24_H12))
+    }
+
+    /// Convert the alarm hours to the 12h/24h format of the clock hours
+    /// where they differ.
+    ///
+    /// The clock hours and both alarm hours registers are read. The hours
+    /// register of each alarm using a different format than the clock is
+    /// rewritten in the clock format, keeping the same time of day.
+    /// Returns whether any alarm was changed. See
+    /// [`alarm_format_matches_clock()`](#method.alarm_format_matches_clock).
+    pub fn harmonize_formats(&mut self) -> Result<bool, Error<E>> {
+        let clock = self.iface.read_register(Register::HOURS)?;
+        let clock_hours = hours_from_register(clock);
+        let mut changed = false;
+        for &alarm in &[Alarm::Zero, Alarm::One] {
+            let register = alarm_base(alarm) + AlarmOffset::HOURS;
+            let data = self.iface.read_register(register)?;
+            if (clock & BitFlags::H24_H12) != (data & BitFlags::H24_H12) {
+                let hours = hours_to_h24(hours_from_register(data & 0b0111_1111));
+                let value = hours_to_register(h24_to_hours(hours, clock_hours))?;
+                self.iface.write_register(register, value)?;
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Read the raw contents of the alarm 1 registers (ALM1SEC to ALM1MTH).
+    ///
+    /// This is intended for debugging. No decoding is done.
+    pub fn read_alarm1_raw(&mut self, buf: &mut [u8; 6]) -> Result<(), Error<E>> {
+        self.read_alarm_registers(Alarm::One, buf)
+    }
+
+    /// Check whether any of the alarms has been configured.
+    ///
+    /// Returns `true` if the registers of at least one alarm differ from
+    /// their power-on reset value (all zero). The alarm interrupt flags are
+    /// not taken into account.
+    pub fn alarms_are_configured(&mut self) -> Result<bool, Error<E>> {
+        let mut alarm0 = [0; 6];
+        let mut alarm1 = [0; 6];
+        self.read_alarm_registers(Alarm::Zero, &mut alarm0)?;
+        self.read_alarm_registers(Alarm::One, &mut alarm1)?;
+        Ok(is_configured(&alarm0) || is_configured(&alarm1))
+    }
+
+    /// Set alarm 0 to trigger every day at `hour:minute:second`.
+    ///
+    /// The device can only match a single field or all fields at once, so
+    /// this is emulated by setting alarm 0 with `AlarmMatching::AllMatch`
+    /// to the next occurrence of the given time and enabling it.
+    /// Since the date must match as well, the alarm only triggers once.
+    /// After it triggers, the interrupt handler must call
+    /// `reschedule_emulated_daily_alarm0()` to arm it for the following day.
+    /// The hour is converted to the hour format (12h/24h) the clock is
+    /// running in, as otherwise the alarm would never match.
+    pub fn set_emulated_daily_alarm0(
+        &mut self,
+        hour: Hours,
+        minute: u8,
+        second: u8,
+    ) -> Result<(), Error<E>> {
+        hours_to_register(hour)?;
+        Self::check_lt(minute, 60)?;
+        Self::check_lt(second, 60)?;
+        let now = self.get_datetime()?;
+        let hour = h24_to_hours(hours_to_h24(hour), now.hour);
+        let next = next_time_of_day(&now, hour, minute, second);
+        self.write_alarm0_all_match(&next)?;
+        self.enable_alarm(Alarm::Zero)
+    }
+
+    /// Arm the daily alarm set with `set_emulated_daily_alarm0()` for its
+    /// next occurrence.
+    ///
+    /// The time of day is read back from the alarm 0 registers. This clears
+    /// the alarm 0 interrupt flag.
+    pub fn reschedule_emulated_daily_alarm0(&mut self) -> Result<(), Error<E>> {
+        let mut data = [0; 6];
+        self.read_alarm_registers(Alarm::Zero, &mut data)?;
+        let second = packed_bcd_to_decimal(data[AlarmOffset::SECONDS as usize]);
+        let minute = packed_bcd_to_decimal(data[AlarmOffset::MINUTES as usize]);
+        let hour = hours_from_register(data[AlarmOffset::HOURS as usize]);
+        self.set_emulated_daily_alarm0(hour, minute, second)
+    }
+
+    /// Swap the configuration of alarm 0 and alarm 1.
+    ///
+    /// Both alarm register sets are read and each is written to the other's
+    /// location, including the matching configuration and interrupt flag.
+    /// The output polarity (ALMPOL) only exists in the alarm 0 registers and
+    /// applies to both alarms, so it stays in place. The alarm enables in the
+    /// CONTROL register are not changed.
+    ///
+    /// Note that this takes several transactions, so an alarm may trigger
+    /// while the registers are being rewritten.
+    pub fn swap_alarms(&mut self) -> Result<(), Error<E>> {
+        let mut alarm0 = [0; 6];
+        let mut alarm1 = [0; 6];
+        self.read_alarm_registers(Alarm::Zero, &mut alarm0)?;
+        self.read_alarm_registers(Alarm::One, &mut alarm1)?;
+        let weekday = AlarmOffset::WEEKDAY as usize;
+        let polarity = alarm0[weekday] & BitFlags::ALMPOL;
+        alarm0[weekday] &= !BitFlags::ALMPOL;
+        alarm1[weekday] = (alarm1[weekday] & !BitFlags::ALMPOL) | polarity;
+        self.write_alarm_registers(Alarm::Zero, &alarm1)?;
+        self.write_alarm_registers(Alarm::One, &alarm0)
+    }
+
+    /// Reset both alarms to their power-on reset configuration.
+    ///
+    /// Both alarms are disabled and all their registers cleared, which also
+    /// clears the interrupt flags and the output polarity (ALMPOL).
+    /// The time-keeping, trimming and square-wave configuration are not
+    /// changed.
+    pub fn reset_alarms(&mut self) -> Result<(), Error<E>> {
+        self.modify_register(
+            Register::CONTROL,
+            BitFlags::ALM0EN | BitFlags::ALM1EN,
+            false,
+        )?;
+        self.write_alarm_registers(Alarm::Zero, &[0; 6])?;
+        self.write_alarm_registers(Alarm::One, &[0; 6])
+    }
+
+    // Write alarm 0 matching all fields of `datetime`. The alarm interrupt
+    // flag is cleared and the output polarity kept.
+    fn write_alarm0_all_match(&mut self, datetime: &DateTime) -> Result<(), Error<E>> {
+        let datetime = AlarmDateTime {
+            month: datetime.month,
+            day: datetime.day,
+            weekday: datetime.weekday,
+            hour: datetime.hour,
+            minute: datetime.minute,
+            second: datetime.second,
+        };
+        self.set_alarm(Alarm::Zero, &datetime, AlarmMatching::AllMatch)
+    }
+
+    // Validate and write the registers of an alarm. The alarm interrupt flag
+    // is cleared and the output polarity (only in alarm 0) kept.
+    fn set_alarm(
+        &mut self,
+        alarm: Alarm,
+        datetime: &AlarmDateTime,
+        matching: AlarmMatching,
+    ) -> Result<(), Error<E>> {
+        let mut data = alarm_to_registers(datetime, matching)?;
+        if let Alarm::Zero = alarm {
+            let polarity = self.iface.read_register(Register::ALM0WKDAY)? & BitFlags::ALMPOL;
+            data[AlarmOffset::WEEKDAY as usize] |= polarity;
+        }
+        self.write_alarm_registers(alarm, &data)
+    }
+
+    // Read and decode the registers of an alarm
+    fn get_alarm(&mut self, alarm: Alarm) -> Result<(AlarmDateTime, AlarmMatching), Error<E>> {
+        let mut data = [0; 6];
+        self.read_alarm_registers(alarm, &mut data)?;
+        let weekday = data[AlarmOffset::WEEKDAY as usize];
+        let matching = alarm_matching_from_register(weekday)?;
+        let datetime = AlarmDateTime {
+            month: packed_bcd_to_decimal(data[AlarmOffset::MONTH as usize] & 0b0001_1111),
+            day: packed_bcd_to_decimal(data[AlarmOffset::DAY as usize] & 0b0011_1111),
+            weekday: weekday & BitFlags::WEEKDAY,
+            hour: hours_from_register(data[AlarmOffset::HOURS as usize] & 0b0111_1111),
+            minute: packed_bcd_to_decimal(data[AlarmOffset::MINUTES as usize] & 0b0111_1111),
+            second: packed_bcd_to_decimal(data[AlarmOffset::SECONDS as usize] & 0b0111_1111),
+        };
+        Ok((datetime, matching))
+    }
+
+    // Write the seconds to month registers of an alarm
+    fn write_alarm_registers(&mut self, alarm: Alarm, data: &[u8; 6]) -> Result<(), Error<E>> {
+        let mut payload = [0; 7];
+        payload[0] = alarm_base(alarm);
+        payload[1..].copy_from_slice(data);
+        self.iface.write_data(&mut payload)
+    }
+
+    // Read the seconds to month registers of an alarm
+    fn read_alarm_registers(&mut self, alarm: Alarm, buf: &mut [u8; 6]) -> Result<(), Error<E>> {
+        let mut data = [0; 7];
+        data[0] = alarm_base(alarm);
+        self.iface.read_data(&mut data)?;
+        buf.copy_from_slice(&data[1..]);
+        Ok(())
+    }
+}
+
+/// Encode a date and time into the contents of the alarm registers
+/// (ALMxSEC to ALMxMTH) for the given matching configuration.
+///
+/// This does not need a device, so that alarm register images can be
+/// precomputed. The year is not stored. The hour is encoded in the 12h/24h
+/// format it is given in. The interrupt flag (ALMIF) and output polarity
+/// (ALMPOL) bits are left cleared.
+/// Returns `Error::InvalidInputData` if any of the fields is out of range.
+pub fn alarm_registers_for<E>(
+    datetime: &DateTime,
+    matching: AlarmMatching,
+) -> Result<[u8; 6], Error<E>> {
+    let datetime = AlarmDateTime {
+        month: datetime.month,
+        day: datetime.day,
+        weekday: datetime.weekday,
+        hour: datetime.hour,
+        minute: datetime.minute,
+        second: datetime.second,
+    };
+    alarm_to_registers(&datetime, matching)
+}
+
+// Validate and encode the seconds to month registers of an alarm. The
+// interrupt flag (ALMIF) and output polarity (ALMPOL) are left cleared.
+pub(crate) fn alarm_to_registers<E>(
+    datetime: &AlarmDateTime,
+    matching: AlarmMatching,
+) -> Result<[u8; 6], Error<E>> {
+    check_lt(datetime.second, 60)?;
+    check_lt(datetime.minute, 60)?;
+    check_between(datetime.weekday, 1, 7)?;
+    check_between(datetime.day, 1, 31)?;
+    check_between(datetime.month, 1, 12)?;
+    Ok([
+        decimal_to_packed_bcd(datetime.second),
+        decimal_to_packed_bcd(datetime.minute),
+        hours_to_register(datetime.hour)?,
+        alarm_matching_to_register(matching) | datetime.weekday,
+        decimal_to_packed_bcd(datetime.day),
+        decimal_to_packed_bcd(datetime.month),
+    ])
+}
+
+fn alarm_matching_from_register<E>(data: u8) -> Result<AlarmMatching, Error<E>> {
+    match (data & BitFlags::ALMMSK) >> 4 {
+        0b000 => Ok(AlarmMatching::SecondsMatch),
+        0b001 => Ok(AlarmMatching::MinutesMatch),
+        0b010 => Ok(AlarmMatching::HoursMatch),
+        0b011 => Ok(AlarmMatching::WeekdayMatch),
+        0b100 => Ok(AlarmMatching::DayMatch),
+        0b111 => Ok(AlarmMatching::AllMatch),
+        _ => Err(Error::InvalidInputData),
+    }
+}
+
+fn alarm_matching_to_register(matching: AlarmMatching) -> u8 {
+    let mask = match matching {
+        AlarmMatching::SecondsMatch => 0b000,
+        AlarmMatching::MinutesMatch => 0b001,
+        AlarmMatching::HoursMatch => 0b010,
+        AlarmMatching::WeekdayMatch => 0b011,
+        AlarmMatching::DayMatch => 0b100,
+        AlarmMatching::AllMatch => 0b111,
+    };
+    mask << 4
+}
+
+fn is_configured(registers: &[u8; 6]) -> bool {
+    registers.iter().enumerate().any(|(i, value)| {
+        if i == AlarmOffset::WEEKDAY as usize {
+            value & !BitFlags::ALMIF != 0
+        } else {
+            *value != 0
+        }
+    })
+}
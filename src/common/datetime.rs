@@ -1,10 +1,22 @@
 //! Common date/time function
 
-use super::super::{BitFlags, DateTime, Error, Hours, Mcp794xx, Register, Rtcc};
-use super::{decimal_to_packed_bcd, hours_from_register, hours_to_register, packed_bcd_to_decimal};
+use super::super::{
+    ic, BitFlags, DateTime, Error, HourFormat, Hours, Mcp794xx, Register, Rtcc, Weekday,
+    WeekdayConvention,
+};
+use super::datetime_math::{
+    add_days, add_seconds, cmp_datetime, convert_weekday, days_in_month, h24_to_hours,
+    hours_to_h24, next_time_of_day, to_timestamp, weekday,
+};
+use super::{
+    decimal_to_packed_bcd, hours_from_register, hours_to_register, is_packed_bcd,
+    packed_bcd_to_decimal,
+};
+use core::cmp::Ordering;
+use hal::blocking::delay::DelayMs;
 use interface;
 
-impl<DI, E> Rtcc for Mcp794xx<DI>
+impl<DI, IC, E> Rtcc for Mcp794xx<DI, IC>
 where
     DI: interface::WriteData<Error = Error<E>> + interface::ReadData<Error = Error<E>>,
 {
@@ -27,19 +39,23 @@ where
     }
 
     fn get_weekday(&mut self) -> Result<u8, Self::Error> {
-        Err(Error::InvalidInputData)
+        let data = self.iface.read_register(Register::WEEKDAY)?;
+        Ok(data & BitFlags::WEEKDAY)
     }
 
     fn get_day(&mut self) -> Result<u8, Self::Error> {
-        Err(Error::InvalidInputData)
+        let day = self.iface.read_register(Register::DAY)?;
+        Ok(packed_bcd_to_decimal(day))
     }
 
     fn get_month(&mut self) -> Result<u8, Self::Error> {
-        Err(Error::InvalidInputData)
+        let month = self.iface.read_register(Register::MONTH)?;
+        Ok(packed_bcd_to_decimal(month & !BitFlags::LPYR))
     }
 
     fn get_year(&mut self) -> Result<u16, Self::Error> {
-        Err(Error::InvalidInputData)
+        let year = self.iface.read_register(Register::YEAR)?;
+        Ok(self.century_base + u16::from(packed_bcd_to_decimal(year)))
     }
 
     fn set_seconds(&mut self, seconds: u8) -> Result<(), Self::Error> {
@@ -64,27 +80,680 @@ where
         self.iface.write_register(Register::HOURS, value)
     }
 
+    /// Set the weekday [1-7].
+    ///
+    /// The status bits sharing the register (VBATEN, etc.) are preserved.
     fn set_weekday(&mut self, weekday: u8) -> Result<(), Self::Error> {
-        Err(Error::InvalidInputData)
+        Self::check_between(weekday, 1, 7)?;
+        let data = self.iface.read_register(Register::WEEKDAY)?;
+        let value = (data & !BitFlags::WEEKDAY) | weekday;
+        self.iface.write_register(Register::WEEKDAY, value)
     }
 
     fn set_day(&mut self, day: u8) -> Result<(), Self::Error> {
-        Err(Error::InvalidInputData)
+        Self::check_between(day, 1, 31)?;
+        self.iface
+            .write_register(Register::DAY, decimal_to_packed_bcd(day))
     }
 
     fn set_month(&mut self, month: u8) -> Result<(), Self::Error> {
-        Err(Error::InvalidInputData)
+        Self::check_between(month, 1, 12)?;
+        self.iface
+            .write_register(Register::MONTH, decimal_to_packed_bcd(month))
     }
 
+    /// Set the year within the configured century
+    /// (by default [2000-2099]).
     fn set_year(&mut self, year: u16) -> Result<(), Self::Error> {
-        Err(Error::InvalidInputData)
+        self.check_year(year)?;
+        let year = decimal_to_packed_bcd((year - self.century_base) as u8);
+        self.iface.write_register(Register::YEAR, year)
     }
 
     fn get_datetime(&mut self) -> Result<DateTime, Self::Error> {
-        Err(Error::InvalidInputData)
+        let mut data = [0; 8];
+        data[0] = Register::SECONDS;
+        self.iface.read_data(&mut data)?;
+        let datetime = decode_datetime(&data[1..]);
+        Ok(DateTime {
+            year: datetime.year - 2000 + self.century_base,
+            ..datetime
+        })
     }
 
+    /// Set the date and time.
+    ///
+    /// The registers are read first so that the oscillator state (ST) and
+    /// the status bits sharing the weekday register (VBATEN, etc.) are
+    /// preserved. If a valid time marker address is configured, the marker
+    /// is written to SRAM afterwards. See
+    /// [`set_valid_marker_address()`](#method.set_valid_marker_address).
     fn set_datetime(&mut self, datetime: &DateTime) -> Result<(), Self::Error> {
-        Err(Error::InvalidInputData)
+        let (mut payload, current_seconds) = self.datetime_payload(datetime)?;
+        payload[Register::SECONDS as usize + 1] |= current_seconds & BitFlags::ST;
+        self.iface.write_data(&mut payload)?;
+        self.write_valid_marker()
     }
 }
+
+// Decode the contents of the time-keeping registers (RTCSEC to RTCYEAR)
+fn decode_datetime(data: &[u8]) -> DateTime {
+    DateTime {
+        year: 2000 + u16::from(packed_bcd_to_decimal(data[Register::YEAR as usize])),
+        month: packed_bcd_to_decimal(data[Register::MONTH as usize] & !BitFlags::LPYR),
+        day: packed_bcd_to_decimal(data[Register::DAY as usize]),
+        weekday: data[Register::WEEKDAY as usize] & BitFlags::WEEKDAY,
+        hour: hours_from_register(data[Register::HOURS as usize]),
+        minute: packed_bcd_to_decimal(data[Register::MINUTES as usize]),
+        second: packed_bcd_to_decimal(data[Register::SECONDS as usize] & !BitFlags::ST),
+    }
+}
+
+/// Encode a date and time into the contents of the time-keeping registers
+/// (RTCSEC to RTCYEAR) in packed BCD format.
+///
+/// The oscillator (ST) and status (VBATEN, etc.) bits are left cleared.
+/// Returns `Error::InvalidInputData` if any of the fields is out of range.
+pub fn datetime_to_registers<E>(datetime: &DateTime) -> Result<[u8; 7], Error<E>> {
+    if !(2000..=2099).contains(&datetime.year)
+        || !(1..=12).contains(&datetime.month)
+        || !(1..=31).contains(&datetime.day)
+        || !(1..=7).contains(&datetime.weekday)
+        || datetime.minute > 59
+        || datetime.second > 59
+    {
+        return Err(Error::InvalidInputData);
+    }
+    Ok([
+        decimal_to_packed_bcd(datetime.second),
+        decimal_to_packed_bcd(datetime.minute),
+        hours_to_register(datetime.hour)?,
+        datetime.weekday,
+        decimal_to_packed_bcd(datetime.day),
+        decimal_to_packed_bcd(datetime.month),
+        decimal_to_packed_bcd((datetime.year - 2000) as u8),
+    ])
+}
+
+impl<DI, IC, E> Mcp794xx<DI, IC>
+where
+    DI: interface::WriteData<Error = Error<E>> + interface::ReadData<Error = Error<E>>,
+{
+    /// Set the century of the years stored in the device.
+    ///
+    /// The device only stores the last two digits of the year. The driver
+    /// adds `century_base` to them, so that years
+    /// [`century_base`-`century_base + 99`] can be represented.
+    /// The default is 2000. The value must be a multiple of 100 up to 9900,
+    /// otherwise `Error::InvalidInputData` is returned.
+    ///
+    /// Note that the device considers every year divisible by 4 a leap
+    /// year, which does not hold for e.g. 2100.
+    pub fn set_century_base(&mut self, century_base: u16) -> Result<(), Error<E>> {
+        if century_base / 100 * 100 != century_base || century_base > 9900 {
+            return Err(Error::InvalidInputData);
+        }
+        self.century_base = century_base;
+        Ok(())
+    }
+
+    /// Get the century of the years stored in the device.
+    pub fn get_century_base(&self) -> u16 {
+        self.century_base
+    }
+
+    /// Read whether the current year is a leap year (LPYR).
+    ///
+    /// The device sets this flag when the year register is divisible by 4.
+    /// It then rolls over from February 29th instead of 28th.
+    pub fn is_leap_year(&mut self) -> Result<bool, Error<E>> {
+        let data = self.iface.read_register(Register::MONTH)?;
+        Ok((data & BitFlags::LPYR) != 0)
+    }
+
+    /// Earliest date and time representable with the configured century
+    /// base: January 1st of `century_base` at 00:00:00.
+    ///
+    /// The weekday is numbered following the weekday convention of the
+    /// driver (ISO 8601 by default).
+    pub fn min_datetime(&self) -> DateTime {
+        self.new_h24_datetime(self.century_base, 1, 1, 0, 0, 0)
+    }
+
+    /// Latest date and time representable with the configured century
+    /// base: December 31st of `century_base + 99` at 23:59:59.
+    ///
+    /// The weekday is numbered following the weekday convention of the
+    /// driver (ISO 8601 by default).
+    pub fn max_datetime(&self) -> DateTime {
+        self.new_h24_datetime(self.century_base + 99, 12, 31, 23, 59, 59)
+    }
+
+    fn new_h24_datetime(
+        &self,
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> DateTime {
+        DateTime {
+            year,
+            month,
+            day,
+            weekday: weekday(year, month, day, self.weekday_convention),
+            hour: Hours::H24(hour),
+            minute,
+            second,
+        }
+    }
+
+    /// Read the date and time, checking the integrity of the registers.
+    ///
+    /// All time-keeping registers are read in one burst and every BCD digit
+    /// is validated before decoding. Returns `Error::InvalidInputData` if any
+    /// register holds invalid BCD or an out-of-range value, as may happen
+    /// after corruption or if the device was never initialized.
+    pub fn get_datetime_checked(&mut self) -> Result<DateTime, Error<E>> {
+        let mut data = [0; 8];
+        data[0] = Register::SECONDS;
+        self.iface.read_data(&mut data)?;
+        let mut registers = [0; 7];
+        registers.copy_from_slice(&data[1..]);
+        let datetime = registers_to_datetime(&registers)?;
+        Ok(DateTime {
+            year: datetime.year - 2000 + self.century_base,
+            ..datetime
+        })
+    }
+
+    /// Set the date and time aligned to an external 1PPS edge.
+    ///
+    /// `datetime` is the date and time at the edge. The oscillator is
+    /// stopped and all time-keeping registers are written in advance. Then
+    /// `wait_for_pps` is called, which must block until the rising edge of
+    /// the 1PPS signal. Immediately afterwards the seconds register is
+    /// written again to start the oscillator, so that the seconds count
+    /// starts at the edge.
+    ///
+    /// The status bits sharing the weekday register (VBATEN, etc.) are
+    /// preserved and the oscillator is left enabled. If a valid time marker
+    /// address is configured, the marker is written to SRAM afterwards. See
+    /// [`set_valid_marker_address()`](#method.set_valid_marker_address).
+    pub fn set_datetime_on_pps<F: FnOnce()>(
+        &mut self,
+        datetime: &DateTime,
+        wait_for_pps: F,
+    ) -> Result<(), Error<E>> {
+        let (mut payload, _) = self.datetime_payload(datetime)?;
+        self.iface.write_data(&mut payload)?;
+        let seconds = payload[Register::SECONDS as usize + 1] | BitFlags::ST;
+        wait_for_pps();
+        self.iface.write_register(Register::SECONDS, seconds)?;
+        self.is_enabled = true;
+        self.write_valid_marker()
+    }
+
+    /// Set the date and time without reading the device first.
+    ///
+    /// All time-keeping registers are written in one burst without the
+    /// read that [`set_datetime()`](#method.set_datetime) does beforehand,
+    /// which roughly halves the bus traffic. This is intended for setting
+    /// the clock at boot, when the state of the device is known.
+    ///
+    /// The tradeoff is that nothing is preserved from the device:
+    /// - The oscillator (ST) bit is taken from the driver, so it is only set
+    ///   if the oscillator was enabled through this driver instance (e.g.
+    ///   with [`enable()`](#method.enable)). A freshly created driver assumes
+    ///   that the oscillator is disabled, and writing the date and time
+    ///   then stops it.
+    /// - The status bits sharing the weekday register are cleared. In
+    ///   particular, the battery backup (VBATEN) is disabled.
+    pub fn set_datetime_unchecked(&mut self, datetime: &DateTime) -> Result<(), Error<E>> {
+        let registers = self.datetime_registers(datetime)?;
+        let mut payload = [Register::SECONDS, 0, 0, 0, 0, 0, 0, 0];
+        payload[1..].copy_from_slice(&registers);
+        if self.is_enabled {
+            payload[Register::SECONDS as usize + 1] |= BitFlags::ST;
+        }
+        self.iface.write_data(&mut payload)?;
+        self.write_valid_marker()
+    }
+
+    /// Set up the device as a battery-backed wall clock in one call.
+    ///
+    /// The sequence is:
+    /// 1. Read the time-keeping registers.
+    /// 2. Write `datetime` with the hour converted to `format`, together
+    ///    with the battery backup enabled (VBATEN), the power failure flag
+    ///    (PWRFAIL) cleared and the oscillator enabled (ST), in one burst.
+    ///    On devices without battery backup (MCP7940M), VBATEN is left
+    ///    cleared.
+    /// 3. Write the valid time marker to SRAM, if a marker address is
+    ///    configured. See
+    ///    [`set_valid_marker_address()`](#method.set_valid_marker_address).
+    /// 4. Poll the OSCRUN bit for up to 1 s.
+    ///
+    /// Returns `Error::OscillatorFailure` if the oscillator does not run
+    /// within 1 s, which indicates a hardware fault such as a missing or
+    /// damaged crystal.
+    /// Returns `Error::InvalidInputData` if any of the fields of `datetime`
+    /// is out of range, before anything is written.
+    pub fn setup_wall_clock<D: DelayMs<u8>>(
+        &mut self,
+        datetime: &DateTime,
+        format: HourFormat,
+        delay: &mut D,
+    ) -> Result<(), Error<E>>
+    where
+        IC: ic::Features,
+    {
+        hours_to_register(datetime.hour)?;
+        let format = match format {
+            HourFormat::H24 => Hours::H24(0),
+            HourFormat::H12 => Hours::AM(12),
+        };
+        let datetime = DateTime {
+            hour: h24_to_hours(hours_to_h24(datetime.hour), format),
+            ..datetime.clone()
+        };
+        let (mut payload, _) = self.datetime_payload(&datetime)?;
+        payload[Register::SECONDS as usize + 1] |= BitFlags::ST;
+        let weekday = &mut payload[Register::WEEKDAY as usize + 1];
+        *weekday &= !BitFlags::PWRFAIL;
+        if IC::HAS_BATTERY_BACKUP {
+            *weekday |= BitFlags::VBATEN;
+        }
+        self.iface.write_data(&mut payload)?;
+        self.is_enabled = true;
+        self.write_valid_marker()?;
+        if self.wait_for_oscillator(delay)? {
+            Ok(())
+        } else {
+            Err(Error::OscillatorFailure)
+        }
+    }
+
+    // Burst payload setting `datetime` with the ST bit cleared and the
+    // status bits of the weekday register preserved, together with the
+    // current content of the seconds register.
+    fn datetime_payload(&mut self, datetime: &DateTime) -> Result<([u8; 8], u8), Error<E>> {
+        let registers = self.datetime_registers(datetime)?;
+        let mut current = [0; 8];
+        current[0] = Register::SECONDS;
+        self.iface.read_data(&mut current)?;
+        let mut payload = [Register::SECONDS, 0, 0, 0, 0, 0, 0, 0];
+        payload[1..].copy_from_slice(&registers);
+        payload[Register::WEEKDAY as usize + 1] |=
+            current[Register::WEEKDAY as usize + 1] & !BitFlags::WEEKDAY;
+        Ok((payload, current[Register::SECONDS as usize + 1]))
+    }
+
+    // Validate and encode `datetime` within the configured century
+    pub(crate) fn datetime_registers(&self, datetime: &DateTime) -> Result<[u8; 7], Error<E>> {
+        self.check_year(datetime.year)?;
+        datetime_to_registers(&DateTime {
+            year: datetime.year - self.century_base + 2000,
+            ..datetime.clone()
+        })
+    }
+
+    fn check_year(&self, year: u16) -> Result<(), Error<E>> {
+        Self::check_between(year, self.century_base, self.century_base + 99)
+    }
+
+    /// Read the raw content of the seconds (RTCSEC) register.
+    ///
+    /// This is intended as a lightweight heartbeat: it is a single
+    /// one-byte read, and observing the value change confirms that the
+    /// clock is ticking. No decoding is done, so the ST bit is included.
+    pub fn read_seconds_byte(&mut self) -> Result<u8, Error<E>> {
+        self.iface.read_register(Register::SECONDS)
+    }
+
+    /// Read the seconds [0-59] together with whether the oscillator is
+    /// enabled (ST bit), from a single register read.
+    pub fn get_seconds_with_run_state(&mut self) -> Result<(u8, bool), Error<E>> {
+        let data = self.iface.read_register(Register::SECONDS)?;
+        Ok((
+            packed_bcd_to_decimal(data & !BitFlags::ST),
+            (data & BitFlags::ST) != 0,
+        ))
+    }
+
+    /// Read the raw content of the day (RTCDATE) register.
+    ///
+    /// No BCD decoding is done.
+    pub fn get_day_raw(&mut self) -> Result<u8, Error<E>> {
+        self.iface.read_register(Register::DAY)
+    }
+
+    /// Read the hours both as a 24h value [0-23] and in the format
+    /// configured in the device.
+    pub fn get_hours_both(&mut self) -> Result<(u8, Hours), Error<E>> {
+        let hours = self.get_hours()?;
+        Ok((hours_to_h24(hours), hours))
+    }
+
+    /// Switch the clock hours to 24h format keeping the time.
+    ///
+    /// The hours are read, converted and written back. Nothing is written
+    /// if they are already in 24h format. Switching right before the hour
+    /// changes may lose that update.
+    pub fn set_24h_mode(&mut self) -> Result<(), Error<E>> {
+        self.set_hour_format(HourFormat::H24)
+    }
+
+    /// Switch the clock hours to 12h format keeping the time.
+    ///
+    /// The hours are read, converted and written back. Nothing is written
+    /// if they are already in 12h format. Switching right before the hour
+    /// changes may lose that update.
+    pub fn set_12h_mode(&mut self) -> Result<(), Error<E>> {
+        self.set_hour_format(HourFormat::H12)
+    }
+
+    fn set_hour_format(&mut self, format: HourFormat) -> Result<(), Error<E>> {
+        let hours = self.get_hours()?;
+        let is_24h = match hours {
+            Hours::H24(_) => true,
+            Hours::AM(_) | Hours::PM(_) => false,
+        };
+        let target = match format {
+            HourFormat::H24 if is_24h => return Ok(()),
+            HourFormat::H12 if !is_24h => return Ok(()),
+            HourFormat::H24 => Hours::H24(0),
+            HourFormat::H12 => Hours::AM(12),
+        };
+        self.set_hours(h24_to_hours(hours_to_h24(hours), target))
+    }
+
+    /// Compute the next occurrence of the given time of day.
+    ///
+    /// Returns today's date at `hour:minute:00` if that is still in the
+    /// future, otherwise tomorrow's. The hour is returned in the format
+    /// given in `hour`.
+    pub fn next_occurrence_of(&mut self, hour: Hours, minute: u8) -> Result<DateTime, Error<E>> {
+        hours_to_register(hour)?;
+        Self::check_lt(minute, 60)?;
+        let now = self.get_datetime()?;
+        Ok(next_time_of_day(&now, hour, minute, 0))
+    }
+
+    /// Compute the difference between the device time and an expected time
+    /// in seconds.
+    ///
+    /// The result is positive if the device is ahead of `expected` and
+    /// negative if it is behind. The weekday is not taken into account.
+    pub fn drift_vs(&mut self, expected: &DateTime) -> Result<i64, Error<E>> {
+        let now = self.get_datetime()?;
+        Ok(to_timestamp(&now) - to_timestamp(expected))
+    }
+
+    /// Get the weekday numbering convention of the driver.
+    pub fn get_weekday_convention(&self) -> WeekdayConvention {
+        self.weekday_convention
+    }
+
+    /// Read the weekday following ISO 8601 (1 = Monday ... 7 = Sunday).
+    ///
+    /// The stored weekday is assumed to follow the weekday convention of
+    /// the driver. Returns `Error::InvalidInputData` if it is out of range.
+    pub fn get_weekday_iso(&mut self) -> Result<u8, Error<E>> {
+        let weekday = self.get_weekday()?;
+        Self::check_between(weekday, 1, 7)?;
+        Ok(convert_weekday(
+            weekday,
+            self.weekday_convention,
+            WeekdayConvention::MondayFirst,
+        ))
+    }
+
+    /// Read the weekday.
+    ///
+    /// The stored weekday is assumed to follow the weekday convention of
+    /// the driver. Returns `Error::InvalidInputData` if it is out of range.
+    pub fn get_weekday_enum(&mut self) -> Result<Weekday, Error<E>> {
+        let weekday = match self.get_weekday_iso()? {
+            1 => Weekday::Monday,
+            2 => Weekday::Tuesday,
+            3 => Weekday::Wednesday,
+            4 => Weekday::Thursday,
+            5 => Weekday::Friday,
+            6 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        };
+        Ok(weekday)
+    }
+
+    /// Set the weekday.
+    ///
+    /// The weekday is stored following the weekday convention of the
+    /// driver. The status bits sharing the register (VBATEN, etc.) are
+    /// preserved.
+    pub fn set_weekday_enum(&mut self, weekday: Weekday) -> Result<(), Error<E>> {
+        let weekday = convert_weekday(
+            weekday as u8,
+            WeekdayConvention::MondayFirst,
+            self.weekday_convention,
+        );
+        self.set_weekday(weekday)
+    }
+
+    /// Remap the weekday stored in the device from one numbering convention
+    /// to another.
+    ///
+    /// The stored weekday is read, converted so that it still designates
+    /// the same day, and written back. The status bits sharing the register
+    /// (VBATEN, etc.) are preserved. Returns `Error::InvalidInputData` if
+    /// the stored weekday is out of range.
+    pub fn change_convention(
+        &mut self,
+        from: WeekdayConvention,
+        to: WeekdayConvention,
+    ) -> Result<(), Error<E>> {
+        let data = self.iface.read_register(Register::WEEKDAY)?;
+        let weekday = data & BitFlags::WEEKDAY;
+        Self::check_between(weekday, 1, 7)?;
+        let weekday = convert_weekday(weekday, from, to);
+        if weekday != data & BitFlags::WEEKDAY {
+            let value = (data & !BitFlags::WEEKDAY) | weekday;
+            self.iface.write_register(Register::WEEKDAY, value)?;
+        }
+        Ok(())
+    }
+
+    /// Set the date and time only if the device time differs from it by
+    /// more than `threshold_seconds`.
+    ///
+    /// This avoids needless updates when regularly synchronizing the device
+    /// to an external time source. Returns whether the date and time was
+    /// written.
+    pub fn set_datetime_if_drifted(
+        &mut self,
+        datetime: &DateTime,
+        threshold_seconds: u32,
+    ) -> Result<bool, Error<E>> {
+        self.datetime_registers(datetime)?;
+        let drift = self.drift_vs(datetime)?;
+        if drift.abs() > i64::from(threshold_seconds) {
+            self.set_datetime(datetime)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Compute the weekday [1-7] on which the date `days_ahead` days after
+    /// the current one falls, numbered following `convention`.
+    ///
+    /// The weekday is derived from the calendar date read from the device,
+    /// not from the device weekday register.
+    pub fn weekday_of_future_date(
+        &mut self,
+        days_ahead: u16,
+        convention: WeekdayConvention,
+    ) -> Result<u8, Error<E>> {
+        let now = self.get_datetime()?;
+        let (year, month, day) = add_days(now.year, now.month, now.day, days_ahead);
+        Ok(weekday(year, month, day, convention))
+    }
+
+    /// Set the date and time from the fields reported by a GPS receiver.
+    ///
+    /// The time is given in 24h format and the clock is set to 24h mode.
+    /// The weekday is computed from the date following the weekday
+    /// convention of the driver (ISO 8601 by default). The date is
+    /// validated against the calendar, including leap years.
+    ///
+    /// The device has no representation for leap seconds, so a second
+    /// value of 60 (as reported by some receivers during a leap second)
+    /// returns `Error::InvalidInputData`.
+    pub fn set_from_gps(
+        &mut self,
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Result<(), Error<E>> {
+        self.check_year(year)?;
+        Self::check_between(month, 1, 12)?;
+        Self::check_between(day, 1, days_in_month(year, month))?;
+        let datetime = self.new_h24_datetime(year, month, day, hour, minute, second);
+        self.set_datetime(&datetime)
+    }
+
+    /// Read the date and time rounded to the nearest minute.
+    ///
+    /// Seconds from 30 onwards round up, carrying over into the minutes,
+    /// hours, day, month and year as needed.
+    pub fn get_datetime_rounded_to_minute(&mut self) -> Result<DateTime, Error<E>> {
+        let datetime = self.get_datetime()?;
+        if datetime.second < 30 {
+            Ok(DateTime {
+                second: 0,
+                ..datetime
+            })
+        } else {
+            Ok(add_seconds(&datetime, u32::from(60 - datetime.second)))
+        }
+    }
+
+    /// Write the current time as "HH:MM:SS" ASCII into `buf`.
+    ///
+    /// The hours are always given in 24h format. The seconds, minutes and
+    /// hours registers are read in one burst. Returns the number of bytes
+    /// written.
+    pub fn format_time_into(&mut self, buf: &mut [u8; 8]) -> Result<usize, Error<E>> {
+        let mut data = [0; 4];
+        data[0] = Register::SECONDS;
+        self.iface.read_data(&mut data)?;
+        let fields = [
+            hours_to_h24(hours_from_register(data[Register::HOURS as usize + 1])),
+            packed_bcd_to_decimal(data[Register::MINUTES as usize + 1]),
+            packed_bcd_to_decimal(data[Register::SECONDS as usize + 1] & !BitFlags::ST),
+        ];
+        for (i, field) in fields.iter().enumerate() {
+            buf[i * 3] = b'0' + field / 10;
+            buf[i * 3 + 1] = b'0' + field % 10;
+            if i < 2 {
+                buf[i * 3 + 2] = b':';
+            }
+        }
+        Ok(buf.len())
+    }
+
+    /// Write the current date as "YYYY-MM-DD" ASCII into `buf`.
+    ///
+    /// The year is given within the configured century. The day, month and
+    /// year registers are read in one burst. Returns the number of bytes
+    /// written.
+    pub fn format_date_into(&mut self, buf: &mut [u8; 10]) -> Result<usize, Error<E>> {
+        let mut data = [0; 4];
+        data[0] = Register::DAY;
+        self.iface.read_data(&mut data)?;
+        let year = self.century_base + u16::from(packed_bcd_to_decimal(data[3]));
+        let month = packed_bcd_to_decimal(data[2] & !BitFlags::LPYR);
+        let day = packed_bcd_to_decimal(data[1]);
+        buf[0] = b'0' + (year / 1000) as u8;
+        buf[1] = b'0' + (year / 100 % 10) as u8;
+        buf[2] = b'0' + (year / 10 % 10) as u8;
+        buf[3] = b'0' + (year % 10) as u8;
+        buf[4] = b'-';
+        buf[5] = b'0' + month / 10;
+        buf[6] = b'0' + month % 10;
+        buf[7] = b'-';
+        buf[8] = b'0' + day / 10;
+        buf[9] = b'0' + day % 10;
+        Ok(buf.len())
+    }
+
+    /// Block until the seconds [0-59] reach `target_second`.
+    ///
+    /// The seconds register is polled every 10 ms, so this returns within
+    /// 10 ms of the second starting. Polling stops after 61 s, which is more
+    /// than a full minute, and `Error::Timeout` is returned. This happens if
+    /// the oscillator is not running.
+    /// Returns `Error::InvalidInputData` if `target_second` is not below 60.
+    pub fn sleep_until_second<D: DelayMs<u8>>(
+        &mut self,
+        target_second: u8,
+        delay: &mut D,
+    ) -> Result<(), Error<E>> {
+        Self::check_lt(target_second, 60)?;
+        for poll in 0..6100 {
+            if poll != 0 {
+                delay.delay_ms(10);
+            }
+            if self.get_seconds()? == target_second {
+                return Ok(());
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    /// Check that the clock advances.
+    ///
+    /// The date and time is read twice, 1.1 s apart, and the second reading
+    /// must be strictly later than the first. Returns `false` if the clock
+    /// is stuck or jumped backwards. The weekday is not taken into account.
+    pub fn check_monotonic<D: DelayMs<u8>>(&mut self, delay: &mut D) -> Result<bool, Error<E>> {
+        let first = self.get_datetime()?;
+        for _ in 0..5 {
+            delay.delay_ms(220);
+        }
+        let second = self.get_datetime()?;
+        Ok(cmp_datetime(&second, &first) == Ordering::Greater)
+    }
+}
+
+/// Decode the contents of the time-keeping registers (RTCSEC to RTCYEAR)
+/// into a date and time.
+///
+/// The oscillator (ST) and status (VBATEN, LPYR, etc.) bits are ignored.
+/// Returns `Error::InvalidInputData` if any register does not hold valid
+/// packed BCD or any of the fields is out of range.
+pub fn registers_to_datetime<E>(registers: &[u8; 7]) -> Result<DateTime, Error<E>> {
+    let hours_mask = if registers[2] & BitFlags::H24_H12 == 0 {
+        0b0011_1111
+    } else {
+        0b0001_1111
+    };
+    let bcd_fields = [
+        registers[0] & !BitFlags::ST,
+        registers[1],
+        registers[2] & hours_mask,
+        registers[4],
+        registers[5] & !BitFlags::LPYR,
+        registers[6],
+    ];
+    if !bcd_fields.iter().all(|field| is_packed_bcd(*field)) {
+        return Err(Error::InvalidInputData);
+    }
+    let datetime = decode_datetime(registers);
+    datetime_to_registers(&datetime)?;
+    Ok(datetime)
+}
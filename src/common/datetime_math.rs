@@ -0,0 +1,331 @@
+//! Date/time arithmetic helpers
+
+use super::super::{DateTime, Hours, WeekdayConvention};
+use core::cmp::Ordering;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+// Number of days since 1970-01-01 (proleptic Gregorian calendar)
+fn days_from_civil(year: u16, month: u8, day: u8) -> i64 {
+    let year = i64::from(year) - if month <= 2 { 1 } else { 0 };
+    let month = i64::from(month);
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_index = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_index + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Date (year, month, day) of a number of days since 1970-01-01
+fn civil_from_days(days: i64) -> (u16, u8, u8) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year as u16, month as u8, day as u8)
+}
+
+// Number of days in a month [28-31]
+pub(crate) fn days_in_month(year: u16, month: u8) -> u8 {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    (days_from_civil(next_year, next_month, 1) - days_from_civil(year, month, 1)) as u8
+}
+
+// ISO 8601 weekday of a date: 1 = Monday ... 7 = Sunday
+pub(crate) fn iso_weekday(year: u16, month: u8, day: u8) -> u8 {
+    // 1970-01-01 was a Thursday
+    ((days_from_civil(year, month, day) + 3) % 7 + 1) as u8
+}
+
+// Weekday of a date in the given numbering convention [1-7]
+pub(crate) fn weekday(year: u16, month: u8, day: u8, convention: WeekdayConvention) -> u8 {
+    let iso = iso_weekday(year, month, day);
+    match convention {
+        WeekdayConvention::MondayFirst => iso,
+        WeekdayConvention::SundayFirst => iso % 7 + 1,
+    }
+}
+
+// Weekday [1-7] numbered following `from` converted to the `to` convention
+pub(crate) fn convert_weekday(weekday: u8, from: WeekdayConvention, to: WeekdayConvention) -> u8 {
+    match (from, to) {
+        (WeekdayConvention::MondayFirst, WeekdayConvention::SundayFirst) => weekday % 7 + 1,
+        (WeekdayConvention::SundayFirst, WeekdayConvention::MondayFirst) => (weekday + 5) % 7 + 1,
+        _ => weekday,
+    }
+}
+
+// Date `days` days after the given one
+pub(crate) fn add_days(year: u16, month: u8, day: u8, days: u16) -> (u16, u8, u8) {
+    civil_from_days(days_from_civil(year, month, day) + i64::from(days))
+}
+
+// Hour in 24h format [0-23]
+pub(crate) fn hours_to_h24(hours: Hours) -> u8 {
+    match hours {
+        Hours::H24(h) => h,
+        Hours::AM(12) => 0,
+        Hours::AM(h) => h,
+        Hours::PM(12) => 12,
+        Hours::PM(h) => h + 12,
+    }
+}
+
+// Hour in 24h format [0-23] expressed in the same format as `format`
+pub(crate) fn h24_to_hours(hour: u8, format: Hours) -> Hours {
+    match format {
+        Hours::H24(_) => Hours::H24(hour),
+        _ if hour == 0 => Hours::AM(12),
+        _ if hour < 12 => Hours::AM(hour),
+        _ if hour == 12 => Hours::PM(12),
+        _ => Hours::PM(hour - 12),
+    }
+}
+
+// Seconds since 1970-01-01T00:00:00
+pub(crate) fn to_timestamp(datetime: &DateTime) -> i64 {
+    days_from_civil(datetime.year, datetime.month, datetime.day) * SECONDS_PER_DAY
+        + i64::from(hours_to_h24(datetime.hour)) * 3600
+        + i64::from(datetime.minute) * 60
+        + i64::from(datetime.second)
+}
+
+// Chronological order of two dates and times. The weekday is ignored.
+pub(crate) fn cmp_datetime(a: &DateTime, b: &DateTime) -> Ordering {
+    to_timestamp(a).cmp(&to_timestamp(b))
+}
+
+// Seconds since 2000-01-01T00:00:00
+pub(crate) fn seconds_since_2000(datetime: &DateTime) -> i64 {
+    to_timestamp(datetime) - 10_957 * SECONDS_PER_DAY
+}
+
+// `datetime` advanced by `seconds`. The hour format is kept and the
+// weekday advanced by the number of days crossed.
+pub(crate) fn add_seconds(datetime: &DateTime, seconds: u32) -> DateTime {
+    let timestamp = to_timestamp(datetime) + i64::from(seconds);
+    let days = timestamp / SECONDS_PER_DAY;
+    let day_seconds = timestamp % SECONDS_PER_DAY;
+    let elapsed_days = days - days_from_civil(datetime.year, datetime.month, datetime.day);
+    let (year, month, day) = civil_from_days(days);
+    DateTime {
+        year,
+        month,
+        day,
+        weekday: ((i64::from(datetime.weekday) - 1 + elapsed_days) % 7 + 1) as u8,
+        hour: h24_to_hours((day_seconds / 3600) as u8, datetime.hour),
+        minute: (day_seconds % 3600 / 60) as u8,
+        second: (day_seconds % 60) as u8,
+    }
+}
+
+// Next instant strictly after `now` at which the time of day is
+// `hour:minute:second`. The hour is returned in the format given in `hour`.
+pub(crate) fn next_time_of_day(now: &DateTime, hour: Hours, minute: u8, second: u8) -> DateTime {
+    let today = DateTime {
+        hour,
+        minute,
+        second,
+        ..now.clone()
+    };
+    if to_timestamp(&today) > to_timestamp(now) {
+        today
+    } else {
+        add_seconds(&today, SECONDS_PER_DAY as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_datetime(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: Hours,
+        minute: u8,
+        second: u8,
+    ) -> DateTime {
+        DateTime {
+            year,
+            month,
+            day,
+            weekday: 1,
+            hour,
+            minute,
+            second,
+        }
+    }
+
+    #[test]
+    fn can_convert_days_from_and_to_civil() {
+        assert_eq!(0, days_from_civil(1970, 1, 1));
+        assert_eq!(10_957, days_from_civil(2000, 1, 1));
+        assert_eq!(11_016, days_from_civil(2000, 2, 29));
+        assert_eq!((2000, 2, 29), civil_from_days(11_016));
+        assert_eq!(
+            (2099, 12, 31),
+            civil_from_days(days_from_civil(2099, 12, 31))
+        );
+    }
+
+    #[test]
+    fn can_get_days_in_month() {
+        assert_eq!(31, days_in_month(2019, 1));
+        assert_eq!(28, days_in_month(2019, 2));
+        assert_eq!(29, days_in_month(2020, 2));
+        assert_eq!(29, days_in_month(2000, 2));
+        assert_eq!(28, days_in_month(2100, 2));
+        assert_eq!(30, days_in_month(2019, 11));
+    }
+
+    #[test]
+    fn can_get_iso_weekday() {
+        assert_eq!(6, iso_weekday(2000, 1, 1));
+        assert_eq!(1, iso_weekday(2019, 3, 4));
+        assert_eq!(7, iso_weekday(2019, 3, 10));
+    }
+
+    #[test]
+    fn can_get_weekday_in_convention() {
+        assert_eq!(1, weekday(2019, 3, 4, WeekdayConvention::MondayFirst));
+        assert_eq!(2, weekday(2019, 3, 4, WeekdayConvention::SundayFirst));
+        assert_eq!(7, weekday(2019, 3, 10, WeekdayConvention::MondayFirst));
+        assert_eq!(1, weekday(2019, 3, 10, WeekdayConvention::SundayFirst));
+        assert_eq!(7, weekday(2019, 3, 9, WeekdayConvention::SundayFirst));
+    }
+
+    #[test]
+    fn can_convert_weekday() {
+        let monday = WeekdayConvention::MondayFirst;
+        let sunday = WeekdayConvention::SundayFirst;
+        assert_eq!(1, convert_weekday(7, monday, sunday));
+        assert_eq!(2, convert_weekday(1, monday, sunday));
+        assert_eq!(7, convert_weekday(6, monday, sunday));
+        assert_eq!(7, convert_weekday(1, sunday, monday));
+        assert_eq!(1, convert_weekday(2, sunday, monday));
+        assert_eq!(6, convert_weekday(7, sunday, monday));
+        assert_eq!(3, convert_weekday(3, sunday, sunday));
+    }
+
+    #[test]
+    fn can_add_days() {
+        assert_eq!((2019, 3, 5), add_days(2019, 3, 4, 1));
+        assert_eq!((2020, 3, 1), add_days(2020, 2, 28, 2));
+        assert_eq!((2020, 1, 1), add_days(2019, 12, 31, 1));
+    }
+
+    #[test]
+    fn can_convert_hours() {
+        assert_eq!(0, hours_to_h24(Hours::AM(12)));
+        assert_eq!(11, hours_to_h24(Hours::AM(11)));
+        assert_eq!(12, hours_to_h24(Hours::PM(12)));
+        assert_eq!(23, hours_to_h24(Hours::PM(11)));
+        assert_eq!(Hours::AM(12), h24_to_hours(0, Hours::PM(1)));
+        assert_eq!(Hours::PM(12), h24_to_hours(12, Hours::AM(1)));
+        assert_eq!(Hours::PM(1), h24_to_hours(13, Hours::AM(1)));
+        assert_eq!(Hours::H24(13), h24_to_hours(13, Hours::H24(1)));
+    }
+
+    #[test]
+    fn can_add_seconds_with_carry() {
+        let dt = new_datetime(2018, 12, 31, Hours::H24(23), 59, 30);
+        let result = add_seconds(&dt, 30);
+        assert_eq!(
+            new_datetime(2019, 1, 1, Hours::H24(0), 0, 0).year,
+            result.year
+        );
+        assert_eq!(
+            DateTime {
+                weekday: 2,
+                ..new_datetime(2019, 1, 1, Hours::H24(0), 0, 0)
+            },
+            result
+        );
+    }
+
+    #[test]
+    fn can_add_seconds_across_leap_day() {
+        let dt = new_datetime(2020, 2, 28, Hours::PM(11), 0, 0);
+        let result = add_seconds(&dt, 3600);
+        assert_eq!(
+            DateTime {
+                weekday: 2,
+                ..new_datetime(2020, 2, 29, Hours::AM(12), 0, 0)
+            },
+            result
+        );
+    }
+
+    #[test]
+    fn weekday_wraps_around() {
+        let dt = DateTime {
+            weekday: 7,
+            ..new_datetime(2019, 3, 3, Hours::H24(12), 0, 0)
+        };
+        assert_eq!(1, add_seconds(&dt, 86_400).weekday);
+    }
+
+    #[test]
+    fn next_time_of_day_is_today_if_still_ahead() {
+        let now = new_datetime(2019, 3, 4, Hours::H24(6), 30, 0);
+        assert_eq!(
+            new_datetime(2019, 3, 4, Hours::H24(7), 0, 15),
+            next_time_of_day(&now, Hours::H24(7), 0, 15)
+        );
+    }
+
+    #[test]
+    fn next_time_of_day_crosses_day_boundary() {
+        let now = new_datetime(2019, 3, 4, Hours::H24(7), 0, 15);
+        assert_eq!(
+            DateTime {
+                weekday: 2,
+                ..new_datetime(2019, 3, 5, Hours::H24(7), 0, 15)
+            },
+            next_time_of_day(&now, Hours::H24(7), 0, 15)
+        );
+    }
+
+    #[test]
+    fn next_time_of_day_crosses_year_boundary() {
+        let now = new_datetime(2019, 12, 31, Hours::PM(11), 59, 59);
+        assert_eq!(
+            DateTime {
+                weekday: 2,
+                ..new_datetime(2020, 1, 1, Hours::AM(12), 0, 0)
+            },
+            next_time_of_day(&now, Hours::AM(12), 0, 0)
+        );
+    }
+
+    #[test]
+    fn can_compare_datetimes() {
+        let earlier = new_datetime(2019, 12, 31, Hours::PM(11), 59, 59);
+        let later = new_datetime(2020, 1, 1, Hours::H24(0), 0, 0);
+        assert_eq!(Ordering::Less, cmp_datetime(&earlier, &later));
+        assert_eq!(Ordering::Greater, cmp_datetime(&later, &earlier));
+        let same = DateTime {
+            weekday: 5,
+            hour: Hours::H24(23),
+            ..earlier.clone()
+        };
+        assert_eq!(Ordering::Equal, cmp_datetime(&earlier, &same));
+    }
+}
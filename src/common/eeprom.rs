@@ -0,0 +1,258 @@
+//! EEPROM functions
+
+use super::super::{ic, DetectedVariant, Error, Mcp794xx, Register};
+use interface;
+
+const EEPROM_SIZE: u8 = 128;
+const EEPROM_PAGE_SIZE: u8 = 8;
+
+// Address of the EUI in the protected EEPROM block. An EUI-48 takes the last
+// 6 bytes, an EUI-64 all 8. Unprogrammed bytes read as 0xFF.
+const EUI_ADDRESS: u8 = 0xF0;
+const EUI_LEN: usize = 8;
+
+// Address of the EEPROM STATUS register and its block protection bits
+// (BP1:BP0)
+const EEPROM_STATUS_ADDRESS: u8 = 0xFF;
+const EEPROM_BLOCK_PROTECT: u8 = 0b0000_1100;
+
+impl<DI, IC, E> Mcp794xx<DI, IC>
+where
+    DI: interface::EepromData<Error = Error<E>>,
+    IC: ic::Features,
+{
+    /// Read data from the EEPROM.
+    ///
+    /// `address` is the EEPROM address [0-127] of the first byte to read.
+    /// The whole buffer is read in one burst. Returns
+    /// `Error::InvalidInputData` if `address + buf.len()` exceeds 128 and
+    /// `Error::FeatureNotAvailable` if the device has no EEPROM.
+    pub fn read_eeprom(&mut self, address: u8, buf: &mut [u8]) -> Result<(), Error<E>> {
+        Self::check_eeprom_range(address, buf.len())?;
+        let mut data = [0; EEPROM_SIZE as usize + 1];
+        let data = &mut data[..=buf.len()];
+        data[0] = address;
+        self.iface.read_eeprom(data)?;
+        buf.copy_from_slice(&data[1..]);
+        Ok(())
+    }
+
+    /// Write data to the EEPROM.
+    ///
+    /// `address` is the EEPROM address [0-127] of the first byte to write.
+    /// The EEPROM is organized in 8-byte pages and a write cannot cross a
+    /// page boundary, so `Error::InvalidInputData` is returned if the data
+    /// does not fit in the page of `address`. It is also returned if
+    /// `address + data.len()` exceeds 128. Returns
+    /// `Error::FeatureNotAvailable` if the device has no EEPROM.
+    ///
+    /// The device then takes up to 5 ms to complete the write cycle, during
+    /// which the EEPROM does not acknowledge its address. The caller must
+    /// wait before accessing the EEPROM again.
+    pub fn write_eeprom(&mut self, address: u8, data: &[u8]) -> Result<(), Error<E>> {
+        Self::check_eeprom_range(address, data.len())?;
+        let page_end = usize::from(address / EEPROM_PAGE_SIZE + 1) * usize::from(EEPROM_PAGE_SIZE);
+        if usize::from(address) + data.len() > page_end {
+            return Err(Error::InvalidInputData);
+        }
+        let mut payload = [0; EEPROM_PAGE_SIZE as usize + 1];
+        let payload = &mut payload[..=data.len()];
+        payload[0] = address;
+        payload[1..].copy_from_slice(data);
+        self.iface.write_eeprom(payload)
+    }
+
+    /// Read whether the block write protection of the EEPROM is active.
+    ///
+    /// Returns `true` if any of the block protection bits (BP1:BP0) in the
+    /// EEPROM STATUS register is set. Writes to the protected part of the
+    /// EEPROM are then ignored by the device: the upper quarter, the upper
+    /// half or the whole array depending on the setting. Returns
+    /// `Error::FeatureNotAvailable` if the device has no EEPROM.
+    pub fn is_eeprom_protected(&mut self) -> Result<bool, Error<E>> {
+        if !IC::HAS_EEPROM {
+            return Err(Error::FeatureNotAvailable);
+        }
+        let mut data = [EEPROM_STATUS_ADDRESS, 0];
+        self.iface.read_eeprom(&mut data)?;
+        Ok((data[1] & EEPROM_BLOCK_PROTECT) != 0)
+    }
+
+    fn check_eeprom_range(address: u8, len: usize) -> Result<(), Error<E>> {
+        if !IC::HAS_EEPROM {
+            Err(Error::FeatureNotAvailable)
+        } else if usize::from(address) + len > usize::from(EEPROM_SIZE) {
+            Err(Error::InvalidInputData)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<DI, IC, E> Mcp794xx<DI, IC>
+where
+    DI: interface::WriteData<Error = Error<E>>
+        + interface::ReadData<Error = Error<E>>
+        + interface::EepromData<Error = Error<E>>,
+    IC: ic::Features,
+{
+    /// Save an oscillator trim value to the EEPROM.
+    ///
+    /// The value is stored as a single two's complement byte at the EEPROM
+    /// address `eeprom_addr` [0-127] so that it survives a loss of both the
+    /// main supply and the battery. This does not change the trimming of
+    /// the device. See
+    /// [`load_and_apply_trim_from_eeprom()`](#method.load_and_apply_trim_from_eeprom).
+    ///
+    /// Returns `Error::InvalidInputData` for -128, which cannot be applied,
+    /// and `Error::FeatureNotAvailable` if the device has no EEPROM. As with
+    /// [`write_eeprom()`](#method.write_eeprom), the caller must wait for
+    /// the write cycle to complete before accessing the EEPROM again.
+    pub fn save_trim_to_eeprom(&mut self, eeprom_addr: u8, trim: i8) -> Result<(), Error<E>> {
+        if trim == i8::MIN {
+            return Err(Error::InvalidInputData);
+        }
+        self.write_eeprom(eeprom_addr, &[trim as u8])
+    }
+
+    /// Read an oscillator trim value saved with
+    /// [`save_trim_to_eeprom()`](#method.save_trim_to_eeprom) and apply it
+    /// with [`set_oscillator_trim()`](#method.set_oscillator_trim).
+    ///
+    /// Returns the applied value. Note that an erased EEPROM byte (0xFF)
+    /// reads as a trim of -1. Returns `Error::InvalidInputData` if the
+    /// stored byte is 0x80 (-128) and `Error::FeatureNotAvailable` if the
+    /// device has no EEPROM.
+    pub fn load_and_apply_trim_from_eeprom(&mut self, eeprom_addr: u8) -> Result<i8, Error<E>> {
+        let mut data = [0];
+        self.read_eeprom(eeprom_addr, &mut data)?;
+        let trim = data[0] as i8;
+        self.set_oscillator_trim(trim)?;
+        Ok(trim)
+    }
+}
+
+impl<DI, IC, E> Mcp794xx<DI, IC>
+where
+    DI: interface::EepromData<Error = Error<E>>,
+    IC: ic::HasEui48,
+{
+    /// Read the preprogrammed EUI-48 node address.
+    ///
+    /// The address is read from the protected EEPROM block (0xF2-0xF7).
+    /// Only available on devices with EUI-48 (MCP79401 and MCP79411).
+    pub fn read_eui48(&mut self) -> Result<[u8; 6], Error<E>> {
+        let mut data = [0; 7];
+        data[0] = EUI_ADDRESS + 2;
+        self.iface.read_eeprom(&mut data)?;
+        let mut eui = [0; 6];
+        eui.copy_from_slice(&data[1..]);
+        Ok(eui)
+    }
+}
+
+impl<DI, IC, E> Mcp794xx<DI, IC>
+where
+    DI: interface::EepromData<Error = Error<E>>,
+    IC: ic::HasEui64,
+{
+    /// Read the preprogrammed EUI-64 node address.
+    ///
+    /// The address is read from the protected EEPROM block (0xF0-0xF7).
+    /// Only available on devices with EUI-64 (MCP79402 and MCP79412).
+    pub fn read_eui64(&mut self) -> Result<[u8; 8], Error<E>> {
+        self.read_eui_area()
+    }
+}
+
+impl<DI, IC, E> Mcp794xx<DI, IC>
+where
+    DI: interface::EepromData<Error = Error<E>>,
+{
+    // Read the whole EUI area of the protected EEPROM block
+    pub(crate) fn read_eui_area(&mut self) -> Result<[u8; EUI_LEN], Error<E>> {
+        let mut data = [0; EUI_LEN + 1];
+        data[0] = EUI_ADDRESS;
+        self.iface.read_eeprom(&mut data)?;
+        let mut eui = [0; EUI_LEN];
+        eui.copy_from_slice(&data[1..]);
+        Ok(eui)
+    }
+}
+
+impl<DI, IC, E> Mcp794xx<DI, IC>
+where
+    DI: interface::ReadData<Error = Error<E>> + interface::EepromData<Error = Error<E>>,
+{
+    /// Guess the device variant by probing the bus.
+    ///
+    /// The RTCC is probed first by reading the seconds register. Any error
+    /// there is returned. Then the EUI in the protected EEPROM block is read
+    /// from the EEPROM I2C address:
+    /// - If this fails with a communication error, the EEPROM address is
+    ///   assumed not to be acknowledged, which means the device has no
+    ///   EEPROM.
+    /// - If all bytes are unprogrammed (0xFF), the device is assumed to have
+    ///   an EEPROM but no EUI.
+    /// - If only the first 2 bytes are unprogrammed, an EUI-48 is assumed,
+    ///   otherwise an EUI-64.
+    ///
+    /// This is a best guess only. The MCP79400, MCP79401 and MCP79402 also
+    /// answer on the EEPROM address for their protected block and cannot be
+    /// told apart from the MCP79410, MCP79411 and MCP79412. An EUI area which
+    /// has been erased or rewritten by the user is misdetected as well.
+    /// Finally, any communication error on the EEPROM address, for example
+    /// because of bus noise, is taken as the device not having an EEPROM.
+    pub fn detect_variant(&mut self) -> Result<DetectedVariant, Error<E>> {
+        self.iface.read_register(Register::SECONDS)?;
+        let eui = match self.read_eui_area() {
+            Ok(eui) => eui,
+            Err(Error::Comm(_)) => return Ok(DetectedVariant::Mcp7940x),
+            Err(e) => return Err(e),
+        };
+        if eui.iter().all(|&byte| byte == 0xFF) {
+            Ok(DetectedVariant::Mcp7941x)
+        } else if eui[..2] == [0xFF, 0xFF] {
+            Ok(DetectedVariant::Eui48)
+        } else {
+            Ok(DetectedVariant::Eui64)
+        }
+    }
+}
+
+impl<DI, IC, E> Mcp794xx<DI, IC>
+where
+    DI: interface::WriteData<Error = Error<E>> + interface::EepromData<Error = Error<E>>,
+    IC: ic::HasEuiBlock,
+{
+    /// Write an EUI node address to the protected EEPROM block.
+    ///
+    /// `eui` must be either 6 bytes long (EUI-48, written to 0xF2-0xF7) or
+    /// 8 bytes long (EUI-64, written to 0xF0-0xF7) so that it can be read
+    /// back with [`read_eui48()`](#method.read_eui48) or
+    /// [`read_eui64()`](#method.read_eui64). The block is unlocked by
+    /// writing 0x55 and then 0xAA to the EEUNLOCK register, which only
+    /// permits the immediately following write. This overwrites any
+    /// preprogrammed EUI.
+    ///
+    /// Devices with a preprogrammed EUI only accept an EUI of the same type:
+    /// 6 bytes on the MCP79401 and MCP79411, 8 bytes on the MCP79402 and
+    /// MCP79412. The MCP79400 and MCP79410 accept both.
+    ///
+    /// The device then takes up to 5 ms to complete the write cycle.
+    /// Returns `Error::InvalidInputData` if `eui` has a different length.
+    pub fn write_eui(&mut self, eui: &[u8]) -> Result<(), Error<E>> {
+        let address = match eui.len() {
+            6 if !IC::HAS_EUI64 => EUI_ADDRESS + 2,
+            EUI_LEN if !IC::HAS_EUI48 => EUI_ADDRESS,
+            _ => return Err(Error::InvalidInputData),
+        };
+        let mut payload = [0; EUI_LEN + 1];
+        let payload = &mut payload[..=eui.len()];
+        payload[0] = address;
+        payload[1..].copy_from_slice(eui);
+        self.iface.write_register(Register::EEUNLOCK, 0x55)?;
+        self.iface.write_register(Register::EEUNLOCK, 0xAA)?;
+        self.iface.write_eeprom(payload)
+    }
+}
@@ -1,5 +1,35 @@
-use super::{BitFlags, Error, Hours};
+use super::{BitFlags, Error, Hours, SqWaveFreq};
+pub mod alarm;
 pub mod datetime;
+pub(crate) mod datetime_math;
+pub mod eeprom;
+pub mod power_fail;
+pub mod reconcile;
+pub mod self_test;
+pub mod sram;
+pub mod status;
+
+// Returns `Error::InvalidInputData` unless `value < reference`
+pub(crate) fn check_lt<T: PartialOrd, E>(value: T, reference: T) -> Result<(), Error<E>> {
+    if !(value < reference) {
+        Err(Error::InvalidInputData)
+    } else {
+        Ok(())
+    }
+}
+
+// Returns `Error::InvalidInputData` unless `lower <= value <= upper`
+pub(crate) fn check_between<T: PartialOrd, E>(
+    value: T,
+    lower: T,
+    upper: T,
+) -> Result<(), Error<E>> {
+    if value < lower || value > upper {
+        Err(Error::InvalidInputData)
+    } else {
+        Ok(())
+    }
+}
 
 // Transforms a decimal number to packed BCD format
 pub(crate) fn decimal_to_packed_bcd(dec: u8) -> u8 {
@@ -11,6 +41,11 @@ pub(crate) fn packed_bcd_to_decimal(bcd: u8) -> u8 {
     (bcd >> 4) * 10 + (bcd & 0xF)
 }
 
+// Whether both nibbles of a packed BCD number are valid decimal digits
+pub(crate) fn is_packed_bcd(bcd: u8) -> bool {
+    bcd >> 4 <= 9 && bcd & 0xF <= 9
+}
+
 pub(crate) fn hours_to_register<E>(hours: Hours) -> Result<u8, Error<E>> {
     match hours {
         Hours::H24(h) if h > 23 => Err(Error::InvalidInputData),
@@ -36,6 +71,16 @@ pub(crate) fn hours_from_register(data: u8) -> Hours {
     }
 }
 
+// Value of the SQWFS bits of the CONTROL register for a frequency
+pub(crate) fn sq_wave_freq_to_register(frequency: SqWaveFreq) -> u8 {
+    match frequency {
+        SqWaveFreq::_1Hz => 0b00,
+        SqWaveFreq::_4_096Hz => 0b01,
+        SqWaveFreq::_8_192Hz => 0b10,
+        SqWaveFreq::_32_768Hz => 0b11,
+    }
+}
+
 fn is_24h_format(hours_data: u8) -> bool {
     hours_data & BitFlags::H24_H12 == 0
 }
@@ -61,6 +106,14 @@ mod tests {
         assert_eq!(59, packed_bcd_to_decimal(0b0101_1001));
     }
 
+    #[test]
+    fn can_validate_packed_bcd() {
+        assert!(is_packed_bcd(0b0000_0000));
+        assert!(is_packed_bcd(0b1001_1001));
+        assert!(!is_packed_bcd(0b0000_1010));
+        assert!(!is_packed_bcd(0b1010_0000));
+    }
+
     #[test]
     fn can_convert_decimal_to_packed_bcd() {
         assert_eq!(0b0000_0000, decimal_to_packed_bcd(0));
@@ -0,0 +1,146 @@
+//! Power-fail timestamp functions
+
+use super::super::{
+    ic, BitFlags, DateTime, Error, Hours, Mcp794xx, PowerFailDateTime, Register, Rtcc,
+};
+use super::datetime_math::{hours_to_h24, to_timestamp};
+use super::{hours_from_register, packed_bcd_to_decimal};
+use interface;
+
+impl<DI, IC, E> Mcp794xx<DI, IC>
+where
+    DI: interface::WriteData<Error = Error<E>> + interface::ReadData<Error = Error<E>>,
+    IC: ic::Features,
+{
+    /// Duration of the last power outage in seconds.
+    ///
+    /// The power-down and power-up timestamps have neither year nor
+    /// seconds fields, so the result has a resolution of one minute and
+    /// the outage is assumed to be shorter than a month. The current year
+    /// is used to resolve the length of February.
+    ///
+    /// Returns `None` if no power failure was recorded (PWRFAIL flag not
+    /// set), if the timestamps are invalid or if they lie in months which
+    /// are not consecutive, since the duration is ambiguous then.
+    /// Returns `Error::FeatureNotAvailable` on devices without battery
+    /// backup (MCP7940M).
+    pub fn power_outage_duration(&mut self) -> Result<Option<u32>, Error<E>> {
+        Self::check_battery_backup()?;
+        let weekday = self.iface.read_register(Register::WEEKDAY)?;
+        if weekday & BitFlags::PWRFAIL == 0 {
+            return Ok(None);
+        }
+        let (down, up) = self.get_power_fail_timestamps()?;
+        let year = self.get_year()?;
+        Ok(outage_duration(year, &down, &up))
+    }
+
+    /// Read the power-down and power-up timestamps.
+    ///
+    /// All timestamp registers (PWRDNMIN to PWRUPMTH) are read in one burst.
+    /// Returns `(power_down, power_up)`. No validation is done.
+    /// Returns `Error::FeatureNotAvailable` on devices without battery
+    /// backup (MCP7940M).
+    pub fn get_power_fail_timestamps(
+        &mut self,
+    ) -> Result<(PowerFailDateTime, PowerFailDateTime), Error<E>> {
+        Self::check_battery_backup()?;
+        let mut data = [0; 9];
+        data[0] = Register::PWRDNMIN;
+        self.iface.read_data(&mut data)?;
+        Ok((decode_timestamp(&data[1..5]), decode_timestamp(&data[5..9])))
+    }
+
+    /// Read the power-down timestamp (PWRDNMIN to PWRDNMTH).
+    ///
+    /// This is the time at which the last power failure occurred.
+    /// No validation is done. Returns `Error::FeatureNotAvailable` on
+    /// devices without battery backup (MCP7940M).
+    pub fn get_power_down_datetime(&mut self) -> Result<PowerFailDateTime, Error<E>> {
+        self.read_timestamp(Register::PWRDNMIN)
+    }
+
+    /// Read the power-up timestamp (PWRUPMIN to PWRUPMTH).
+    ///
+    /// This is the time at which power was restored after the last power
+    /// failure. No validation is done. Returns
+    /// `Error::FeatureNotAvailable` on devices without battery backup
+    /// (MCP7940M).
+    pub fn get_power_up_datetime(&mut self) -> Result<PowerFailDateTime, Error<E>> {
+        self.read_timestamp(Register::PWRUPMIN)
+    }
+
+    // Read and decode the four timestamp registers starting at `base`
+    fn read_timestamp(&mut self, base: u8) -> Result<PowerFailDateTime, Error<E>> {
+        Self::check_battery_backup()?;
+        let mut data = [0; 5];
+        data[0] = base;
+        self.iface.read_data(&mut data)?;
+        Ok(decode_timestamp(&data[1..]))
+    }
+
+    // The power-fail flag and timestamps are only captured by devices with
+    // a battery switchover
+    pub(crate) fn check_battery_backup() -> Result<(), Error<E>> {
+        if IC::HAS_BATTERY_BACKUP {
+            Ok(())
+        } else {
+            Err(Error::FeatureNotAvailable)
+        }
+    }
+}
+
+// Decode the minutes, hours, date and weekday/month timestamp registers
+fn decode_timestamp(data: &[u8]) -> PowerFailDateTime {
+    PowerFailDateTime {
+        minute: packed_bcd_to_decimal(data[0] & 0b0111_1111),
+        hour: hours_from_register(data[1] & 0b0111_1111),
+        day: packed_bcd_to_decimal(data[2] & 0b0011_1111),
+        weekday: data[3] >> 5,
+        month: packed_bcd_to_decimal(data[3] & 0b0001_1111),
+    }
+}
+
+fn is_valid(timestamp: &PowerFailDateTime) -> bool {
+    let hour_is_valid = match timestamp.hour {
+        Hours::H24(h) => h < 24,
+        Hours::AM(h) | Hours::PM(h) => (1..=12).contains(&h),
+    };
+    hour_is_valid
+        && timestamp.minute < 60
+        && (1..=31).contains(&timestamp.day)
+        && (1..=12).contains(&timestamp.month)
+}
+
+fn to_datetime(year: u16, timestamp: &PowerFailDateTime) -> DateTime {
+    DateTime {
+        year,
+        month: timestamp.month,
+        day: timestamp.day,
+        weekday: 1,
+        hour: Hours::H24(hours_to_h24(timestamp.hour)),
+        minute: timestamp.minute,
+        second: 0,
+    }
+}
+
+// Outage duration in seconds, assuming the power came back in `year`.
+fn outage_duration(year: u16, down: &PowerFailDateTime, up: &PowerFailDateTime) -> Option<u32> {
+    if !is_valid(down) || !is_valid(up) {
+        return None;
+    }
+    let down_year = if up.month == down.month || up.month == down.month + 1 {
+        year
+    } else if down.month == 12 && up.month == 1 {
+        year.checked_sub(1)?
+    } else {
+        return None;
+    };
+    let duration =
+        to_timestamp(&to_datetime(year, up)) - to_timestamp(&to_datetime(down_year, down));
+    if duration < 0 {
+        None
+    } else {
+        Some(duration as u32)
+    }
+}
@@ -0,0 +1,178 @@
+//! Declarative configuration functions
+
+use super::super::{
+    AlarmDateTime, AlarmMatching, BitFlags, Error, FullConfig, Mcp794xx, OutputPolarity, Register,
+    SchedulingConfig,
+};
+use super::alarm::alarm_to_registers;
+use super::sq_wave_freq_to_register;
+use interface;
+
+// Number of registers from RTCSEC (0x00) to ALM1MTH (0x16)
+const SNAPSHOT_LEN: usize = 23;
+
+// Number of registers from CONTROL (0x07) to ALM1MTH (0x16)
+const SCHEDULING_LEN: usize = 16;
+
+// Number of registers of an alarm (ALMxSEC to ALMxMTH)
+const ALARM_LEN: usize = 6;
+
+impl<DI, IC, E> Mcp794xx<DI, IC>
+where
+    DI: interface::WriteData<Error = Error<E>> + interface::ReadData<Error = Error<E>>,
+{
+    /// Apply a full configuration, writing only the registers that differ.
+    ///
+    /// All registers from RTCSEC to ALM1MTH are read in one burst and
+    /// compared against `desired`. Each differing register is then written
+    /// individually. Returns the number of registers written, so that
+    /// applying the same configuration again returns 0.
+    ///
+    /// The oscillator (ST), status (OSCRUN, PWRFAIL, VBATEN), leap year
+    /// (LPYR) and alarm interrupt flag (ALMIF) bits are kept as they are.
+    /// Since the clock keeps running, the time-keeping registers will
+    /// usually differ if `desired.datetime` is set.
+    /// Returns `Error::InvalidInputData` if any of the fields is out of
+    /// range, before anything is read or written.
+    pub fn reconcile(&mut self, desired: &FullConfig) -> Result<usize, Error<E>> {
+        let datetime = match desired.datetime {
+            Some(ref datetime) => Some(self.datetime_registers(datetime)?),
+            None => None,
+        };
+        let alarm0 = alarm_to_registers(&desired.alarm0.0, desired.alarm0.1)?;
+        let alarm1 = alarm_to_registers(&desired.alarm1.0, desired.alarm1.1)?;
+
+        let mut data = [0; SNAPSHOT_LEN + 1];
+        data[0] = Register::SECONDS;
+        self.iface.read_data(&mut data)?;
+        let mut current = [0; SNAPSHOT_LEN];
+        current.copy_from_slice(&data[1..]);
+
+        let mut target = current;
+        if let Some(datetime) = datetime {
+            target[..datetime.len()].copy_from_slice(&datetime);
+            target[Register::SECONDS as usize] |=
+                current[Register::SECONDS as usize] & BitFlags::ST;
+            target[Register::WEEKDAY as usize] |=
+                current[Register::WEEKDAY as usize] & !BitFlags::WEEKDAY;
+            target[Register::MONTH as usize] |= current[Register::MONTH as usize] & BitFlags::LPYR;
+        }
+        target[Register::CONTROL as usize] = desired.control;
+        target[Register::OSCTRIM as usize] = desired.trim;
+        let polarity = match desired.alarm_polarity {
+            OutputPolarity::High => BitFlags::ALMPOL,
+            OutputPolarity::Low => 0,
+        };
+        let alarms = [
+            (Register::ALM0SEC, Register::ALM0WKDAY, alarm0, polarity),
+            (Register::ALM1SEC, Register::ALM1WKDAY, alarm1, 0),
+        ];
+        for &(base, weekday, ref registers, polarity) in &alarms {
+            let base = base as usize;
+            let weekday = weekday as usize;
+            target[base..base + registers.len()].copy_from_slice(registers);
+            target[weekday] |= polarity | (current[weekday] & BitFlags::ALMIF);
+        }
+
+        let mut changed = 0;
+        for (address, (current, target)) in current.iter().zip(target.iter()).enumerate() {
+            if current != target {
+                self.iface.write_register(address as u8, *target)?;
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Apply an alarm and square-wave configuration with best-effort
+    /// rollback.
+    ///
+    /// The CONTROL to ALM1MTH registers are read in one burst and saved.
+    /// Then the registers of each configured alarm are written in one burst
+    /// each, followed by the CONTROL register. The alarm interrupt flags are
+    /// cleared and the output polarity and the other bits of the CONTROL
+    /// register (OUT, EXTOSC, CRSTRIM) are kept.
+    ///
+    /// If any of these writes fails, the saved contents of the registers
+    /// written so far, including the failed one, are written back in
+    /// reverse order and the original error is returned. Since the bus is
+    /// not transactional, the restore may fail as well. Its errors are
+    /// ignored. Returns `Error::InvalidInputData` if any of the fields is
+    /// out of range, before anything is read or written.
+    pub fn apply_scheduling_config(&mut self, config: &SchedulingConfig) -> Result<(), Error<E>> {
+        let alarm0 = encode_alarm(&config.alarm0)?;
+        let alarm1 = encode_alarm(&config.alarm1)?;
+
+        let mut data = [0; SCHEDULING_LEN + 1];
+        data[0] = Register::CONTROL;
+        self.iface.read_data(&mut data)?;
+        let mut saved = [0; SCHEDULING_LEN];
+        saved.copy_from_slice(&data[1..]);
+
+        let mut target = saved;
+        let mut blocks = [(0, 0); 3];
+        let mut block_count = 0;
+        let mut control =
+            saved[0] & !(BitFlags::ALM0EN | BitFlags::ALM1EN | BitFlags::SQWEN | BitFlags::SQWFS);
+        let alarms = [
+            (
+                Register::ALM0SEC,
+                alarm0,
+                BitFlags::ALM0EN,
+                BitFlags::ALMPOL,
+            ),
+            (Register::ALM1SEC, alarm1, BitFlags::ALM1EN, 0),
+        ];
+        for &(base, ref registers, enable, kept) in &alarms {
+            if let Some(registers) = *registers {
+                let offset = usize::from(base - Register::CONTROL);
+                target[offset..offset + ALARM_LEN].copy_from_slice(&registers);
+                let weekday = offset + usize::from(Register::ALM0WKDAY - Register::ALM0SEC);
+                target[weekday] |= saved[weekday] & kept;
+                blocks[block_count] = (offset, ALARM_LEN);
+                block_count += 1;
+                control |= enable;
+            }
+        }
+        if let Some(frequency) = config.square_wave {
+            control |= BitFlags::SQWEN | sq_wave_freq_to_register(frequency);
+        }
+        target[0] = control;
+        blocks[block_count] = (0, 1);
+        block_count += 1;
+
+        for (i, &(offset, len)) in blocks[..block_count].iter().enumerate() {
+            if let Err(e) = self.write_image(&target, offset, len) {
+                for &(offset, len) in blocks[..=i].iter().rev() {
+                    let _ = self.write_image(&saved, offset, len);
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    // Write `len` registers of an image of the CONTROL to ALM1MTH registers
+    // in one burst, starting at `offset` from CONTROL
+    fn write_image(
+        &mut self,
+        image: &[u8; SCHEDULING_LEN],
+        offset: usize,
+        len: usize,
+    ) -> Result<(), Error<E>> {
+        let mut payload = [0; ALARM_LEN + 1];
+        let payload = &mut payload[..=len];
+        payload[0] = Register::CONTROL + offset as u8;
+        payload[1..].copy_from_slice(&image[offset..offset + len]);
+        self.iface.write_data(payload)
+    }
+}
+
+fn encode_alarm<E>(
+    alarm: &Option<(AlarmDateTime, AlarmMatching)>,
+) -> Result<Option<[u8; ALARM_LEN]>, Error<E>> {
+    match *alarm {
+        Some((ref datetime, matching)) => Ok(Some(alarm_to_registers(datetime, matching)?)),
+        None => Ok(None),
+    }
+}
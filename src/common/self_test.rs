@@ -0,0 +1,73 @@
+//! Self-test functions
+
+use super::super::{ic, Error, Mcp794xx, Register, SelfTestReport};
+use hal::blocking::delay::DelayMs;
+use interface;
+
+// Pattern written to the first SRAM bytes
+const SRAM_PATTERN: [u8; 2] = [0x55, 0xAA];
+
+impl<DI, IC, E> Mcp794xx<DI, IC>
+where
+    DI: interface::WriteData<Error = Error<E>>
+        + interface::ReadData<Error = Error<E>>
+        + interface::EepromData<Error = Error<E>>,
+    IC: ic::Features,
+{
+    /// Run a self-test of the device.
+    ///
+    /// The following checks are performed in order:
+    /// - The device is probed by reading the seconds register. If this fails
+    ///   with a communication error, the remaining checks are skipped.
+    /// - The oscillator is enabled and the OSCRUN bit is polled for up to
+    ///   1 s. The oscillator is left enabled.
+    /// - A test pattern is written to the first two bytes of SRAM and read
+    ///   back. The original SRAM content is restored afterwards.
+    /// - On devices with a preprogrammed EUI-48 or EUI-64, the EUI is read
+    ///   from the protected EEPROM block. It must not be erased (all 0xFF).
+    ///
+    /// This is intended for manufacturing tests of freshly-assembled boards.
+    pub fn self_test<D: DelayMs<u8>>(&mut self, delay: &mut D) -> Result<SelfTestReport, Error<E>> {
+        let mut report = SelfTestReport {
+            device_responds: false,
+            oscillator_running: false,
+            sram_ok: false,
+            eui_ok: None,
+        };
+        match self.iface.read_register(Register::SECONDS) {
+            Ok(_) => report.device_responds = true,
+            Err(Error::Comm(_)) => return Ok(report),
+            Err(e) => return Err(e),
+        }
+        self.enable()?;
+        report.oscillator_running = self.wait_for_oscillator(delay)?;
+        report.sram_ok = self.test_sram()?;
+        report.eui_ok = self.test_eui()?;
+        Ok(report)
+    }
+
+    fn test_sram(&mut self) -> Result<bool, Error<E>> {
+        let mut original = [0; 2];
+        self.read_sram(0, &mut original)?;
+        self.write_sram(0, &SRAM_PATTERN)?;
+        let mut readback = [0; 2];
+        self.read_sram(0, &mut readback)?;
+        self.write_sram(0, &original)?;
+        Ok(readback == SRAM_PATTERN)
+    }
+
+    fn test_eui(&mut self) -> Result<Option<bool>, Error<E>> {
+        let start = if IC::HAS_EUI64 {
+            0
+        } else if IC::HAS_EUI48 {
+            2
+        } else {
+            return Ok(None);
+        };
+        match self.read_eui_area() {
+            Ok(eui) => Ok(Some(eui[start..].iter().any(|&byte| byte != 0xFF))),
+            Err(Error::Comm(_)) => Ok(Some(false)),
+            Err(e) => Err(e),
+        }
+    }
+}
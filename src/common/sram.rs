@@ -0,0 +1,330 @@
+//! SRAM-backed functions
+
+use super::super::{BitFlags, DateTime, Error, Mcp794xx, Register, Rtcc};
+use super::datetime_math::seconds_since_2000;
+use core::convert::TryFrom;
+use interface;
+
+const SRAM_SIZE: u8 = 64;
+
+/// Offset of the first SRAM byte used by the driver itself
+///
+/// The driver keeps its own records in the last 11 bytes of the SRAM
+/// (offsets 53 to 63, addresses 0x55 to 0x5F):
+/// - 53: oscillator started flag, see
+///   [`oscillator_has_started()`](struct.Mcp794xx.html#method.oscillator_has_started)
+/// - 54-55: last applied trim, see
+///   [`store_applied_trim()`](struct.Mcp794xx.html#method.store_applied_trim)
+/// - 56-59: provisioning time, see
+///   [`set_datetime_and_log_provisioning()`](struct.Mcp794xx.html#method.set_datetime_and_log_provisioning)
+/// - 60-63: boot time, see
+///   [`store_boot_time()`](struct.Mcp794xx.html#method.store_boot_time)
+///
+/// These bytes are only written by the methods listed, so they are free for
+/// application data if those are not used. Otherwise, application data
+/// should be kept below this offset.
+pub const RESERVED_SRAM_START: u8 = 53;
+
+// SRAM offset of the boot time record (4 bytes, big endian seconds since
+// 2000-01-01T00:00:00)
+const BOOT_TIME_OFFSET: u8 = 60;
+
+// SRAM offset of the provisioning time record (4 bytes, big endian seconds
+// since 2000-01-01T00:00:00)
+const PROVISIONING_TIME_OFFSET: u8 = 56;
+
+// SRAM offset of the last applied trim record (1 byte marker followed by the
+// trim value as two's complement)
+const TRIM_RECORD_OFFSET: u8 = 54;
+const TRIM_RECORD_MARKER: u8 = 0xA5;
+
+// SRAM offset of the oscillator started flag
+const OSC_STARTED_OFFSET: u8 = RESERVED_SRAM_START;
+const OSC_STARTED_MARKER: u8 = 0x5A;
+
+// Content of the valid time marker byte
+const VALID_TIME_MARKER: u8 = 0xC3;
+
+/// Fixed region of the SRAM
+///
+/// The region starts at offset `BASE` in SRAM and is `LEN` bytes long.
+/// Regions going beyond the end of the SRAM (`BASE + LEN > 64`) are rejected
+/// at compile time. Regions overlapping the bytes used by the driver itself
+/// (from [`RESERVED_SRAM_START`](constant.RESERVED_SRAM_START.html)) are
+/// accepted, since those are only written by some driver methods. Keep
+/// regions below that offset if these methods are used. This allows
+/// declaring a named SRAM layout:
+///
+/// ```
+/// type Config = mcp794xx::SramRegion<0, 16>;
+/// type Counters = mcp794xx::SramRegion<16, 8>;
+///
+/// let config = [0; Config::SIZE];
+/// # assert_eq!(16, config.len());
+/// ```
+#[derive(Debug, Default)]
+pub struct SramRegion<const BASE: u8, const LEN: u8>;
+
+impl<const BASE: u8, const LEN: u8> SramRegion<BASE, LEN> {
+    /// Size of the region in bytes
+    ///
+    /// Using this constant (for example as the length of a buffer) fails to
+    /// compile if the region goes beyond the end of the SRAM.
+    pub const SIZE: usize = {
+        assert!(
+            BASE as u16 + LEN as u16 <= SRAM_SIZE as u16,
+            "SRAM region out of bounds"
+        );
+        LEN as usize
+    };
+
+    /// Read the whole region.
+    ///
+    /// `buf` must be exactly `LEN` bytes long, otherwise
+    /// `Error::InvalidInputData` is returned.
+    pub fn read<DI, IC, E>(driver: &mut Mcp794xx<DI, IC>, buf: &mut [u8]) -> Result<(), Error<E>>
+    where
+        DI: interface::WriteData<Error = Error<E>> + interface::ReadData<Error = Error<E>>,
+    {
+        if buf.len() != Self::SIZE {
+            return Err(Error::InvalidInputData);
+        }
+        driver.read_sram(BASE, buf)
+    }
+
+    /// Write the whole region.
+    ///
+    /// `data` must be exactly `LEN` bytes long, otherwise
+    /// `Error::InvalidInputData` is returned.
+    pub fn write<DI, IC, E>(driver: &mut Mcp794xx<DI, IC>, data: &[u8]) -> Result<(), Error<E>>
+    where
+        DI: interface::WriteData<Error = Error<E>> + interface::ReadData<Error = Error<E>>,
+    {
+        if data.len() != Self::SIZE {
+            return Err(Error::InvalidInputData);
+        }
+        driver.write_sram(BASE, data)
+    }
+}
+
+impl<DI, IC, E> Mcp794xx<DI, IC>
+where
+    DI: interface::WriteData<Error = Error<E>> + interface::ReadData<Error = Error<E>>,
+{
+    /// Read data from SRAM.
+    ///
+    /// `offset` is the offset in SRAM [0-63] of the first byte to read.
+    /// The whole buffer is read in one burst. Returns
+    /// `Error::InvalidInputData` if `offset + buf.len()` exceeds 64.
+    ///
+    /// Offsets from [`RESERVED_SRAM_START`](constant.RESERVED_SRAM_START.html)
+    /// hold the records of some driver methods and can be read like any
+    /// other SRAM byte.
+    pub fn read_sram(&mut self, offset: u8, buf: &mut [u8]) -> Result<(), Error<E>> {
+        Self::check_sram_range(offset, buf.len())?;
+        let mut data = [0; SRAM_SIZE as usize + 1];
+        let data = &mut data[..=buf.len()];
+        data[0] = Register::SRAM + offset;
+        self.iface.read_data(data)?;
+        buf.copy_from_slice(&data[1..]);
+        Ok(())
+    }
+
+    /// Write data to SRAM.
+    ///
+    /// `offset` is the offset in SRAM [0-63] of the first byte to write.
+    /// The whole data is written in one burst. Returns
+    /// `Error::InvalidInputData` if `offset + data.len()` exceeds 64.
+    ///
+    /// Offsets from [`RESERVED_SRAM_START`](constant.RESERVED_SRAM_START.html)
+    /// hold the records of some driver methods and can be overwritten like any
+    /// other SRAM byte.
+    pub fn write_sram(&mut self, offset: u8, data: &[u8]) -> Result<(), Error<E>> {
+        Self::check_sram_range(offset, data.len())?;
+        let mut payload = [0; SRAM_SIZE as usize + 1];
+        let payload = &mut payload[..=data.len()];
+        payload[0] = Register::SRAM + offset;
+        payload[1..].copy_from_slice(data);
+        self.iface.write_data(payload)
+    }
+
+    /// Read a byte from SRAM.
+    ///
+    /// `offset` is the offset in SRAM [0-63]. Returns
+    /// `Error::InvalidInputData` if it is out of range.
+    pub fn read_sram_byte(&mut self, offset: u8) -> Result<u8, Error<E>> {
+        let mut data = [0];
+        self.read_sram(offset, &mut data)?;
+        Ok(data[0])
+    }
+
+    /// Write a byte to SRAM.
+    ///
+    /// `offset` is the offset in SRAM [0-63]. Returns
+    /// `Error::InvalidInputData` if it is out of range.
+    pub fn write_sram_byte(&mut self, offset: u8, value: u8) -> Result<(), Error<E>> {
+        self.write_sram(offset, &[value])
+    }
+
+    fn check_sram_range(offset: u8, len: usize) -> Result<(), Error<E>> {
+        if usize::from(offset) + len > usize::from(SRAM_SIZE) {
+            Err(Error::InvalidInputData)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Write a byte to SRAM only if it differs from the current content.
+    ///
+    /// `address` is the offset in SRAM [0-63]. Returns whether a write
+    /// was performed.
+    pub fn write_sram_byte_if_changed(&mut self, address: u8, data: u8) -> Result<bool, Error<E>> {
+        let current = self.read_sram_byte(address)?;
+        if current == data {
+            Ok(false)
+        } else {
+            self.write_sram_byte(address, data)?;
+            Ok(true)
+        }
+    }
+
+    /// Use an SRAM byte as valid time marker.
+    ///
+    /// `offset` is the offset in SRAM [0-63] of the marker byte. Once this is
+    /// configured, `set_datetime()` writes a marker (0xC3) to that byte and
+    /// [`has_valid_time()`](#method.has_valid_time) checks it. This allows
+    /// telling a provisioned clock apart from a fresh one. Make sure the byte
+    /// does not overlap other SRAM data. Returns `Error::InvalidInputData` if
+    /// `offset` is out of range.
+    pub fn set_valid_marker_address(&mut self, offset: u8) -> Result<(), Error<E>> {
+        Self::check_lt(offset, SRAM_SIZE)?;
+        self.valid_marker_address = Some(offset);
+        Ok(())
+    }
+
+    /// Check whether the device holds a valid time.
+    ///
+    /// The oscillator must be running (OSCRUN). If a valid time marker
+    /// address is configured, the marker must also be present in SRAM, that
+    /// is, the date and time must have been set since the SRAM content was
+    /// last lost. All the driver methods setting the whole date and time
+    /// write the marker: `set_datetime()`, `set_datetime_unchecked()`,
+    /// `set_datetime_on_pps()` and `setup_wall_clock()`, as well as the
+    /// methods built on them.
+    pub fn has_valid_time(&mut self) -> Result<bool, Error<E>> {
+        let weekday = self.iface.read_register(Register::WEEKDAY)?;
+        if (weekday & BitFlags::OSCRUN) == 0 {
+            return Ok(false);
+        }
+        match self.valid_marker_address {
+            Some(offset) => Ok(self.read_sram_byte(offset)? == VALID_TIME_MARKER),
+            None => Ok(true),
+        }
+    }
+
+    // Write the valid time marker, if configured
+    pub(crate) fn write_valid_marker(&mut self) -> Result<(), Error<E>> {
+        match self.valid_marker_address {
+            Some(offset) => self.write_sram_byte(offset, VALID_TIME_MARKER),
+            None => Ok(()),
+        }
+    }
+
+    /// Store the current date and time as boot time in SRAM.
+    ///
+    /// The boot time is kept in the last 4 bytes of the battery-backed
+    /// SRAM (offsets 60 to 63, addresses 0x5C to 0x5F) so that it survives
+    /// MCU resets. See [`uptime_seconds()`](#method.uptime_seconds).
+    ///
+    /// The boot time is stored as seconds since 2000-01-01T00:00:00, which
+    /// covers dates up to 2136-02-07T06:28:15. Returns
+    /// `Error::InvalidInputData` if the current date lies outside of this
+    /// range, as may happen with a century base other than 2000 or 2100.
+    pub fn store_boot_time(&mut self) -> Result<(), Error<E>> {
+        let now = sram_timestamp(&self.get_datetime()?)?;
+        self.write_sram(BOOT_TIME_OFFSET, &now.to_be_bytes())
+    }
+
+    /// Set the date and time and record it as provisioning time in SRAM.
+    ///
+    /// After setting the date and time as in `set_datetime()`, the same date
+    /// and time is stored as seconds since 2000-01-01T00:00:00 (4 bytes,
+    /// big endian) in the battery-backed SRAM at offsets 56 to 59
+    /// (addresses 0x58 to 0x5B). This allows telling later when the clock
+    /// was last set.
+    ///
+    /// This covers dates from 2000-01-01T00:00:00 up to 2136-02-07T06:28:15.
+    /// Returns `Error::InvalidInputData` for dates outside of this range,
+    /// before anything is written.
+    pub fn set_datetime_and_log_provisioning(
+        &mut self,
+        datetime: &DateTime,
+    ) -> Result<(), Error<E>> {
+        let timestamp = sram_timestamp(datetime)?;
+        self.set_datetime(datetime)?;
+        self.write_sram(PROVISIONING_TIME_OFFSET, &timestamp.to_be_bytes())
+    }
+
+    /// Record the last applied oscillator trim value in SRAM.
+    ///
+    /// The record is kept in the battery-backed SRAM at offsets 54 and 55
+    /// (addresses 0x56 and 0x57): a marker byte (0xA5) followed by the trim
+    /// value. This does not change the trimming of the device. See
+    /// [`get_last_applied_trim()`](#method.get_last_applied_trim).
+    pub fn store_applied_trim(&mut self, trim: i8) -> Result<(), Error<E>> {
+        self.write_sram(TRIM_RECORD_OFFSET, &[TRIM_RECORD_MARKER, trim as u8])
+    }
+
+    /// Read the last applied oscillator trim value recorded with
+    /// [`store_applied_trim()`](#method.store_applied_trim).
+    ///
+    /// Returns `None` if no record is present.
+    pub fn get_last_applied_trim(&mut self) -> Result<Option<i8>, Error<E>> {
+        let mut data = [0; 2];
+        self.read_sram(TRIM_RECORD_OFFSET, &mut data)?;
+        if data[0] == TRIM_RECORD_MARKER {
+            Ok(Some(data[1] as i8))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Check whether the oscillator has ever been running.
+    ///
+    /// The device does not latch whether the oscillator has started, so a
+    /// flag is kept in the battery-backed SRAM at offset 53 (address 0x55).
+    /// If the flag is not set yet, the OSCRUN bit is read and the flag is
+    /// set if the oscillator is running. This distinguishes an oscillator
+    /// which never started from one which is currently stopped.
+    pub fn oscillator_has_started(&mut self) -> Result<bool, Error<E>> {
+        if self.read_sram_byte(OSC_STARTED_OFFSET)? == OSC_STARTED_MARKER {
+            return Ok(true);
+        }
+        let weekday = self.iface.read_register(Register::WEEKDAY)?;
+        if (weekday & BitFlags::OSCRUN) != 0 {
+            self.write_sram_byte(OSC_STARTED_OFFSET, OSC_STARTED_MARKER)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Seconds elapsed since the boot time stored with
+    /// [`store_boot_time()`](#method.store_boot_time).
+    ///
+    /// Returns 0 if the stored boot time lies in the future, for example
+    /// because the clock was set back.
+    pub fn uptime_seconds(&mut self) -> Result<u32, Error<E>> {
+        let now = seconds_since_2000(&self.get_datetime()?);
+        let mut data = [0; 4];
+        self.read_sram(BOOT_TIME_OFFSET, &mut data)?;
+        let boot_time = u32::from_be_bytes(data);
+        let uptime = now - i64::from(boot_time);
+        Ok(if uptime < 0 { 0 } else { uptime as u32 })
+    }
+}
+
+// Seconds since 2000-01-01T00:00:00 as stored in the SRAM time records
+fn sram_timestamp<E>(datetime: &DateTime) -> Result<u32, Error<E>> {
+    u32::try_from(seconds_since_2000(datetime)).map_err(|_| Error::InvalidInputData)
+}
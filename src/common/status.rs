@@ -0,0 +1,235 @@
+//! Common status functions
+
+use super::super::{ic, BitFlags, Error, Mcp794xx, Register, Rtcc, Status};
+use hal::blocking::delay::DelayMs;
+use interface;
+
+// Length of the longest status line: "STOP osc=1 pf=1 vbat=1 24h"
+const STATUS_LINE_MAX_LEN: usize = 26;
+
+impl<DI, IC, E> Mcp794xx<DI, IC>
+where
+    DI: interface::WriteData<Error = Error<E>> + interface::ReadData<Error = Error<E>>,
+    IC: ic::Features,
+{
+    /// Read the device status.
+    ///
+    /// The RTCWKDAY and CONTROL registers are read and decoded into the
+    /// oscillator, power-fail and battery backup flags as well as the alarm
+    /// enables and output configuration.
+    pub fn get_status(&mut self) -> Result<Status, Error<E>> {
+        let weekday = self.iface.read_register(Register::WEEKDAY)?;
+        let control = self.iface.read_register(Register::CONTROL)?;
+        Ok(decode_status(weekday, control))
+    }
+
+    /// Check whether a power failure has occurred (PWRFAIL).
+    ///
+    /// The power-down and power-up timestamps are only valid while this
+    /// flag is set. Returns `Error::FeatureNotAvailable` on devices without
+    /// battery backup (MCP7940M).
+    pub fn has_power_failed(&mut self) -> Result<bool, Error<E>> {
+        Self::check_battery_backup()?;
+        let data = self.iface.read_register(Register::WEEKDAY)?;
+        Ok((data & BitFlags::PWRFAIL) != 0)
+    }
+
+    /// Clear the power failure flag (PWRFAIL).
+    ///
+    /// This re-arms the capture of the power-down and power-up timestamps.
+    /// The weekday, VBATEN and OSCRUN bits sharing the register are
+    /// preserved. Returns `Error::FeatureNotAvailable` on devices without
+    /// battery backup (MCP7940M).
+    pub fn clear_power_failed(&mut self) -> Result<(), Error<E>> {
+        Self::check_battery_backup()?;
+        self.modify_register(Register::WEEKDAY, BitFlags::PWRFAIL, false)
+    }
+
+    /// Check whether the time kept running through the last power failure.
+    ///
+    /// This is inferred from the RTCWKDAY register, read once: the time is
+    /// taken as trustworthy if a power failure was recorded (PWRFAIL),
+    /// meaning the device switched over to the backup supply, the backup
+    /// supply is enabled (VBATEN), without which the clock would not have
+    /// been kept running, and the oscillator is running now (OSCRUN).
+    /// Returns `false` if no power failure was recorded, since nothing can
+    /// then be inferred about the backup supply.
+    ///
+    /// The oscillator status is not latched, so a stop that recovered on
+    /// its own is not detected. The flags are only meaningful if PWRFAIL
+    /// was cleared after the time was last set. See
+    /// [`clear_power_failed()`](#method.clear_power_failed).
+    ///
+    /// Returns `Error::FeatureNotAvailable` on devices without battery
+    /// backup (MCP7940M).
+    pub fn time_survived_power_cycle(&mut self) -> Result<bool, Error<E>> {
+        Self::check_battery_backup()?;
+        let data = self.iface.read_register(Register::WEEKDAY)?;
+        let flags = BitFlags::PWRFAIL | BitFlags::VBATEN | BitFlags::OSCRUN;
+        Ok((data & flags) == flags)
+    }
+
+    /// Read the level of the MFP pin if it is used as a general purpose
+    /// output.
+    ///
+    /// The device does not report the state of the MFP pin. However, when
+    /// the square-wave output and both alarms are disabled the pin is driven
+    /// according to the OUT bit. In that case `Some(level)` is returned,
+    /// otherwise `None`.
+    pub fn get_mfp_level_if_output(&mut self) -> Result<Option<bool>, Error<E>> {
+        let control = self.iface.read_register(Register::CONTROL)?;
+        if control & (BitFlags::SQWEN | BitFlags::ALM0EN | BitFlags::ALM1EN) != 0 {
+            Ok(None)
+        } else {
+            Ok(Some((control & BitFlags::OUT) != 0))
+        }
+    }
+
+    /// Make sure the clock is running, restarting it if it was stopped.
+    ///
+    /// If the oscillator was stopped by software (ST bit clear), it is
+    /// enabled and the OSCRUN bit is polled for up to 1 s. Returns whether
+    /// the oscillator had to be restarted.
+    ///
+    /// Returns `Error::OscillatorFailure` if the oscillator is enabled but
+    /// does not run within 1 s, which indicates a hardware fault such as a
+    /// missing or damaged crystal.
+    pub fn ensure_running<D: DelayMs<u8>>(&mut self, delay: &mut D) -> Result<bool, Error<E>> {
+        let seconds = self.iface.read_register(Register::SECONDS)?;
+        let restarted = if (seconds & BitFlags::ST) == 0 {
+            self.enable()?;
+            true
+        } else {
+            self.is_enabled = true;
+            false
+        };
+        if self.wait_for_oscillator(delay)? {
+            Ok(restarted)
+        } else {
+            Err(Error::OscillatorFailure)
+        }
+    }
+
+    /// Read whether the oscillator is running, debounced over a number of
+    /// samples.
+    ///
+    /// The OSCRUN bit is read `samples` times, waiting 1 ms between reads.
+    /// Returns `true` only if the oscillator was running in all samples.
+    /// Returns `Error::InvalidInputData` if `samples` is 0.
+    pub fn oscillator_running_debounced<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        samples: u8,
+    ) -> Result<bool, Error<E>> {
+        if samples == 0 {
+            return Err(Error::InvalidInputData);
+        }
+        let mut running = true;
+        for sample in 0..samples {
+            if sample != 0 {
+                delay.delay_ms(1);
+            }
+            let data = self.iface.read_register(Register::WEEKDAY)?;
+            running &= (data & BitFlags::OSCRUN) != 0;
+        }
+        Ok(running)
+    }
+
+    /// Check that the seconds advance at roughly one per second.
+    ///
+    /// The seconds register is polled every 10 ms until it changes (for up
+    /// to 1.1 s), then read again after a 2.5 s delay. Returns `true` if it
+    /// advanced by exactly 2 seconds in that time, which tolerates a delay
+    /// or oscillator error of about 20%. Returns `false` if the seconds did
+    /// not change or advanced at a different rate, as happens with a stopped
+    /// or grossly mistuned oscillator.
+    ///
+    /// This takes up to 3.6 s.
+    pub fn verify_seconds_tick_rate<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<bool, Error<E>> {
+        let initial = self.get_seconds()?;
+        let mut start = None;
+        for _ in 0..110 {
+            delay.delay_ms(10);
+            let seconds = self.get_seconds()?;
+            if seconds != initial {
+                start = Some(seconds);
+                break;
+            }
+        }
+        let start = match start {
+            Some(seconds) => seconds,
+            None => return Ok(false),
+        };
+        for _ in 0..10 {
+            delay.delay_ms(250);
+        }
+        let end = self.get_seconds()?;
+        Ok((end + 60 - start) % 60 == 2)
+    }
+
+    /// Write a one-line ASCII summary of the device state into `buf`.
+    ///
+    /// The line has the form "RUN osc=1 pf=0 vbat=1 12h":
+    /// - "RUN" or "STOP" according to the oscillator enable (ST)
+    /// - `osc`: oscillator running (OSCRUN)
+    /// - `pf`: power failure occurred (PWRFAIL)
+    /// - `vbat`: battery backup enabled (VBATEN)
+    /// - "12h" or "24h" according to the hour format
+    ///
+    /// The seconds to weekday registers are read in one burst. Returns the
+    /// number of bytes written. A 26-byte buffer is always large enough,
+    /// `Error::InvalidInputData` is returned if the line does not fit.
+    pub fn status_line(&mut self, buf: &mut [u8]) -> Result<usize, Error<E>> {
+        let mut data = [0; 5];
+        data[0] = Register::SECONDS;
+        self.iface.read_data(&mut data)?;
+        let seconds = data[Register::SECONDS as usize + 1];
+        let hours = data[Register::HOURS as usize + 1];
+        let weekday = data[Register::WEEKDAY as usize + 1];
+        let flag = |mask| if (weekday & mask) != 0 { b'1' } else { b'0' };
+        let parts: [&[u8]; 8] = [
+            if (seconds & BitFlags::ST) != 0 {
+                b"RUN"
+            } else {
+                b"STOP"
+            },
+            b" osc=",
+            &[flag(BitFlags::OSCRUN)],
+            b" pf=",
+            &[flag(BitFlags::PWRFAIL)],
+            b" vbat=",
+            &[flag(BitFlags::VBATEN)],
+            if (hours & BitFlags::H24_H12) != 0 {
+                b" 12h"
+            } else {
+                b" 24h"
+            },
+        ];
+        let mut line = [0; STATUS_LINE_MAX_LEN];
+        let mut len = 0;
+        for part in parts.iter() {
+            line[len..len + part.len()].copy_from_slice(part);
+            len += part.len();
+        }
+        if buf.len() < len {
+            return Err(Error::InvalidInputData);
+        }
+        buf[..len].copy_from_slice(&line[..len]);
+        Ok(len)
+    }
+}
+
+fn decode_status(weekday: u8, control: u8) -> Status {
+    Status {
+        oscillator_running: (weekday & BitFlags::OSCRUN) != 0,
+        power_failed: (weekday & BitFlags::PWRFAIL) != 0,
+        battery_backup_enabled: (weekday & BitFlags::VBATEN) != 0,
+        alarm0_enabled: (control & BitFlags::ALM0EN) != 0,
+        alarm1_enabled: (control & BitFlags::ALM1EN) != 0,
+        square_wave_enabled: (control & BitFlags::SQWEN) != 0,
+        output_level_high: (control & BitFlags::OUT) != 0,
+    }
+}
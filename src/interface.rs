@@ -1,6 +1,10 @@
 //! Communication interface
-use super::{Error, DEVICE_ADDRESS};
+use super::{Error, DEVICE_ADDRESS, EEPROM_ADDRESS};
 use hal::blocking;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "std")]
+use std::vec::Vec;
 
 /// I2C interface
 #[derive(Debug, Default)]
@@ -69,3 +73,117 @@ where
             .map_err(Error::Comm)
     }
 }
+
+/// Access the EEPROM
+///
+/// The EEPROM answers on its own I2C address, separate from the RTCC.
+pub trait EepromData {
+    /// Error type
+    type Error;
+    /// Read some EEPROM data. The first element corresponds to the starting address.
+    fn read_eeprom(&mut self, payload: &mut [u8]) -> Result<(), Self::Error>;
+    /// Write EEPROM data. The first element corresponds to the starting address.
+    fn write_eeprom(&mut self, payload: &[u8]) -> Result<(), Self::Error>;
+}
+
+impl<I2C, E> EepromData for I2cInterface<I2C>
+where
+    I2C: blocking::i2c::Write<Error = E> + blocking::i2c::WriteRead<Error = E>,
+{
+    type Error = Error<E>;
+
+    fn read_eeprom(&mut self, payload: &mut [u8]) -> Result<(), Self::Error> {
+        let len = payload.len();
+        self.i2c
+            .write_read(EEPROM_ADDRESS, &[payload[0]], &mut payload[1..len])
+            .map_err(Error::Comm)
+    }
+
+    fn write_eeprom(&mut self, payload: &[u8]) -> Result<(), Self::Error> {
+        self.i2c.write(EEPROM_ADDRESS, payload).map_err(Error::Comm)
+    }
+}
+
+/// Interface wrapper recording the duration of each transaction
+///
+/// This is intended for profiling the bus access patterns on a host with
+/// `std` support. The transactions are forwarded unchanged to the wrapped
+/// interface.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct TimedInterface<DI> {
+    iface: DI,
+    timings: Vec<Duration>,
+}
+
+#[cfg(feature = "std")]
+impl<DI> TimedInterface<DI> {
+    /// Wrap an interface.
+    pub fn new(iface: DI) -> Self {
+        TimedInterface {
+            iface,
+            timings: Vec::new(),
+        }
+    }
+
+    /// Durations of the transactions performed so far, in order.
+    pub fn timings(&self) -> &[Duration] {
+        &self.timings
+    }
+
+    /// Clear the recorded transaction durations.
+    pub fn clear_timings(&mut self) {
+        self.timings.clear()
+    }
+
+    /// Destroy the wrapper, return the wrapped interface.
+    pub fn destroy(self) -> DI {
+        self.iface
+    }
+
+    fn timed<T, F: FnOnce(&mut DI) -> T>(&mut self, f: F) -> T {
+        let start = Instant::now();
+        let result = f(&mut self.iface);
+        self.timings.push(start.elapsed());
+        result
+    }
+}
+
+#[cfg(feature = "std")]
+impl<DI: WriteData> WriteData for TimedInterface<DI> {
+    type Error = DI::Error;
+
+    fn write_register(&mut self, register: u8, data: u8) -> Result<(), Self::Error> {
+        self.timed(|iface| iface.write_register(register, data))
+    }
+
+    fn write_data(&mut self, payload: &mut [u8]) -> Result<(), Self::Error> {
+        self.timed(|iface| iface.write_data(payload))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<DI: ReadData> ReadData for TimedInterface<DI> {
+    type Error = DI::Error;
+
+    fn read_register(&mut self, register: u8) -> Result<u8, Self::Error> {
+        self.timed(|iface| iface.read_register(register))
+    }
+
+    fn read_data(&mut self, payload: &mut [u8]) -> Result<(), Self::Error> {
+        self.timed(|iface| iface.read_data(payload))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<DI: EepromData> EepromData for TimedInterface<DI> {
+    type Error = DI::Error;
+
+    fn read_eeprom(&mut self, payload: &mut [u8]) -> Result<(), Self::Error> {
+        self.timed(|iface| iface.read_eeprom(payload))
+    }
+
+    fn write_eeprom(&mut self, payload: &[u8]) -> Result<(), Self::Error> {
+        self.timed(|iface| iface.write_eeprom(payload))
+    }
+}
@@ -6,33 +6,436 @@
 #![deny(unsafe_code, missing_docs)]
 #![no_std]
 
+#[cfg(feature = "std")]
+extern crate std;
+
+use core::marker::PhantomData;
 extern crate embedded_hal as hal;
 extern crate rtcc;
 pub use rtcc::{DateTime, Hours, Rtcc};
 
 /// All possible errors in this crate
+///
+/// More variants may be added in the future, so this enum is marked
+/// non-exhaustive and a match on it must include a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error<E> {
     /// I²C/SPI bus error
     Comm(E),
     /// Invalid input data provided
     InvalidInputData,
+    /// Value read back from the device does not match the value written
+    VerificationFailed,
+    /// The oscillator is enabled but does not run
+    OscillatorFailure,
+    /// The feature is not available on this device variant
+    FeatureNotAvailable,
+    /// The device did not reach the expected state in time
+    Timeout,
+}
+
+/// Alarm selection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Alarm {
+    /// Alarm 0
+    Zero,
+    /// Alarm 1
+    One,
+}
+
+/// Alarm matching configuration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlarmMatching {
+    /// Alarm triggers when the seconds match.
+    SecondsMatch,
+    /// Alarm triggers when the minutes match.
+    MinutesMatch,
+    /// Alarm triggers when the hours match.
+    HoursMatch,
+    /// Alarm triggers when the weekday matches.
+    WeekdayMatch,
+    /// Alarm triggers when the day (date) matches.
+    DayMatch,
+    /// Alarm triggers when the seconds, minutes, hours, weekday, day and
+    /// month match.
+    AllMatch,
+}
+
+/// Alarm output polarity of the MFP pin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputPolarity {
+    /// The MFP pin is driven low when an alarm is asserted (ALMPOL = 0).
+    Low,
+    /// The MFP pin is driven high when an alarm is asserted (ALMPOL = 1).
+    High,
+}
+
+/// Numbering convention of the weekdays [1-7]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum WeekdayConvention {
+    /// 1 = Monday ... 7 = Sunday (ISO 8601) (default)
+    #[default]
+    MondayFirst,
+    /// 1 = Sunday ... 7 = Saturday
+    SundayFirst,
+}
+
+/// Day of the week
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Weekday {
+    /// Monday
+    Monday = 1,
+    /// Tuesday
+    Tuesday,
+    /// Wednesday
+    Wednesday,
+    /// Thursday
+    Thursday,
+    /// Friday
+    Friday,
+    /// Saturday
+    Saturday,
+    /// Sunday
+    Sunday,
+}
+
+/// Square-wave output frequency
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SqWaveFreq {
+    /// 1 Hz
+    _1Hz,
+    /// 4.096 kHz
+    _4_096Hz,
+    /// 8.192 kHz
+    _8_192Hz,
+    /// 32.768 kHz
+    _32_768Hz,
+}
+
+/// Hour format of the time-keeping registers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HourFormat {
+    /// 24h format
+    H24,
+    /// 12h format with AM/PM
+    H12,
+}
+
+/// Alarm date and time
+///
+/// The alarms have no year field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlarmDateTime {
+    /// Month [1-12]
+    pub month: u8,
+    /// Day [1-31]
+    pub day: u8,
+    /// Weekday [1-7]
+    pub weekday: u8,
+    /// Hour in 24h/12h format
+    pub hour: Hours,
+    /// Minute [0-59]
+    pub minute: u8,
+    /// Second [0-59]
+    pub second: u8,
+}
+
+/// IC markers
+pub mod ic {
+    use super::private;
+
+    /// Features of a device known at compile time
+    pub trait Features: private::Sealed {
+        /// Whether the device has a battery backup supply input
+        const HAS_BATTERY_BACKUP: bool;
+        /// Whether the device has a user EEPROM
+        const HAS_EEPROM: bool;
+        /// Whether the device has a preprogrammed EUI-48 MAC address
+        const HAS_EUI48: bool;
+        /// Whether the device has a preprogrammed EUI-64 MAC address
+        const HAS_EUI64: bool;
+        /// Whether the device has a protected EEPROM block for an EUI
+        const HAS_EUI_BLOCK: bool;
+        /// Size of the SRAM in bytes
+        const SRAM_SIZE: u8;
+    }
+
+    /// Devices with a preprogrammed EUI-48 MAC address
+    pub trait HasEui48: Features {}
+
+    /// Devices with a preprogrammed EUI-64 MAC address
+    pub trait HasEui64: Features {}
+
+    /// Devices with a protected EEPROM block for an EUI
+    pub trait HasEuiBlock: Features {}
+
+    // Implement the marker trait `$trait` for `$ic` if `$has` is `true`
+    macro_rules! marker_impl {
+        (true, $trait:ident, $ic:ident) => {
+            impl $trait for $ic {}
+        };
+        (false, $trait:ident, $ic:ident) => {};
+    }
+
+    macro_rules! ic_marker {
+        (
+            $ic:ident,
+            $doc:expr,
+            $battery:expr,
+            $eeprom:expr,
+            $eui48:tt,
+            $eui64:tt,
+            $eui_block:tt
+        ) => {
+            #[doc = $doc]
+            #[derive(Debug, Default)]
+            pub struct $ic;
+
+            impl private::Sealed for $ic {}
+
+            impl Features for $ic {
+                const HAS_BATTERY_BACKUP: bool = $battery;
+                const HAS_EEPROM: bool = $eeprom;
+                const HAS_EUI48: bool = $eui48;
+                const HAS_EUI64: bool = $eui64;
+                const HAS_EUI_BLOCK: bool = $eui_block;
+                const SRAM_SIZE: u8 = 64;
+            }
+
+            marker_impl!($eui48, HasEui48, $ic);
+            marker_impl!($eui64, HasEui64, $ic);
+            marker_impl!($eui_block, HasEuiBlock, $ic);
+        };
+    }
+
+    ic_marker!(
+        Mcp7940m,
+        "MCP7940M IC marker",
+        false,
+        false,
+        false,
+        false,
+        false
+    );
+    ic_marker!(
+        Mcp7940n,
+        "MCP7940N IC marker",
+        true,
+        false,
+        false,
+        false,
+        false
+    );
+    ic_marker!(
+        Mcp79400,
+        "MCP79400 IC marker",
+        true,
+        false,
+        false,
+        false,
+        true
+    );
+    ic_marker!(
+        Mcp79401,
+        "MCP79401 IC marker",
+        true,
+        false,
+        true,
+        false,
+        true
+    );
+    ic_marker!(
+        Mcp79402,
+        "MCP79402 IC marker",
+        true,
+        false,
+        false,
+        true,
+        true
+    );
+    ic_marker!(
+        Mcp79410,
+        "MCP79410 IC marker",
+        true,
+        true,
+        false,
+        false,
+        true
+    );
+    ic_marker!(
+        Mcp79411,
+        "MCP79411 IC marker",
+        true,
+        true,
+        true,
+        false,
+        true
+    );
+    ic_marker!(
+        Mcp79412,
+        "MCP79412 IC marker",
+        true,
+        true,
+        false,
+        true,
+        true
+    );
 }
 
 /// MCP794xx RTCC driver
 #[derive(Debug, Default)]
-pub struct Mcp794xx<DI> {
+pub struct Mcp794xx<DI, IC> {
     iface: DI,
     is_enabled: bool,
+    century_base: u16,
+    weekday_convention: WeekdayConvention,
+    valid_marker_address: Option<u8>,
+    _ic: PhantomData<IC>,
+}
+
+/// Device status flags
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Status {
+    /// Oscillator is running (OSCRUN)
+    pub oscillator_running: bool,
+    /// A power failure has been detected (PWRFAIL)
+    pub power_failed: bool,
+    /// Battery backup is enabled (VBATEN)
+    pub battery_backup_enabled: bool,
+    /// Alarm 0 is enabled (ALM0EN)
+    pub alarm0_enabled: bool,
+    /// Alarm 1 is enabled (ALM1EN)
+    pub alarm1_enabled: bool,
+    /// Square-wave output is enabled (SQWEN)
+    pub square_wave_enabled: bool,
+    /// Logic level of the MFP output when not used for the square wave or
+    /// alarms (OUT)
+    pub output_level_high: bool,
+}
+
+/// Result of the device self-test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelfTestReport {
+    /// The device acknowledged a register read.
+    pub device_responds: bool,
+    /// The oscillator started running after being enabled.
+    pub oscillator_running: bool,
+    /// A test pattern written to SRAM could be read back.
+    pub sram_ok: bool,
+    /// The preprogrammed EUI could be read and is not erased. `None` on
+    /// devices without a preprogrammed EUI.
+    pub eui_ok: Option<bool>,
+}
+
+impl SelfTestReport {
+    /// Whether all checks passed
+    pub fn passed(&self) -> bool {
+        self.device_responds
+            && self.oscillator_running
+            && self.sram_ok
+            && self.eui_ok != Some(false)
+    }
+}
+
+/// Features of a device
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capabilities {
+    /// Battery backup supply input
+    pub battery_backup: bool,
+    /// User EEPROM
+    pub eeprom: bool,
+    /// Preprogrammed EUI-48 MAC address
+    pub eui48: bool,
+    /// Preprogrammed EUI-64 MAC address
+    pub eui64: bool,
+    /// Size of the SRAM in bytes
+    pub sram_size: u8,
+}
+
+/// Device variant guessed by probing the bus with `detect_variant()`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DetectedVariant {
+    /// The EEPROM address is not acknowledged: MCP7940M or MCP7940N
+    Mcp7940x,
+    /// The EEPROM address is acknowledged but no EUI is programmed: most
+    /// likely MCP79410 (also MCP79400 or a device with an erased EUI)
+    Mcp7941x,
+    /// A preprogrammed EUI-48 is present: MCP79411 (or MCP79401)
+    Eui48,
+    /// A preprogrammed EUI-64 is present: MCP79412 (or MCP79402)
+    Eui64,
+}
+
+/// Power-fail timestamp (no year or seconds fields in hardware)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerFailDateTime {
+    /// Month [1-12]
+    pub month: u8,
+    /// Day [1-31]
+    pub day: u8,
+    /// Weekday [1-7]
+    pub weekday: u8,
+    /// Hour in 24h/12h format
+    pub hour: Hours,
+    /// Minute [0-59]
+    pub minute: u8,
+}
+
+/// Full device configuration to be applied with `reconcile()`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FullConfig {
+    /// Date and time. If `None`, the time-keeping registers are left as they are.
+    pub datetime: Option<DateTime>,
+    /// Alarm 0 date and time and matching configuration
+    pub alarm0: (AlarmDateTime, AlarmMatching),
+    /// Alarm 1 date and time and matching configuration
+    pub alarm1: (AlarmDateTime, AlarmMatching),
+    /// Alarm output polarity (ALMPOL)
+    pub alarm_polarity: OutputPolarity,
+    /// Raw content of the digital trimming register (OSCTRIM)
+    pub trim: u8,
+    /// Raw content of the CONTROL register
+    pub control: u8,
+}
+
+/// Alarm and square-wave configuration to be applied with
+/// `apply_scheduling_config()`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchedulingConfig {
+    /// Alarm 0 date and time and matching configuration. If `None`, alarm 0
+    /// is disabled and its registers are left as they are.
+    pub alarm0: Option<(AlarmDateTime, AlarmMatching)>,
+    /// Alarm 1 date and time and matching configuration. If `None`, alarm 1
+    /// is disabled and its registers are left as they are.
+    pub alarm1: Option<(AlarmDateTime, AlarmMatching)>,
+    /// Square-wave output frequency. If `None`, the square-wave output is
+    /// disabled.
+    pub square_wave: Option<SqWaveFreq>,
 }
 
 const DEVICE_ADDRESS: u8 = 0b1101111;
+const EEPROM_ADDRESS: u8 = 0b1010111;
 
 struct Register;
 impl Register {
     const SECONDS: u8 = 0x00;
     const MINUTES: u8 = 0x01;
     const HOURS: u8 = 0x02;
+    const WEEKDAY: u8 = 0x03;
+    const DAY: u8 = 0x04;
+    const MONTH: u8 = 0x05;
+    const YEAR: u8 = 0x06;
+    const CONTROL: u8 = 0x07;
+    const OSCTRIM: u8 = 0x08;
+    const EEUNLOCK: u8 = 0x09;
+    const ALM0SEC: u8 = 0x0A;
+    const ALM0WKDAY: u8 = 0x0D;
+    const ALM1SEC: u8 = 0x11;
+    const ALM1WKDAY: u8 = 0x14;
+    const PWRDNMIN: u8 = 0x18;
+    const PWRUPMIN: u8 = 0x1C;
+    const SRAM: u8 = 0x20;
 }
 
 struct BitFlags;
@@ -40,58 +443,437 @@ impl BitFlags {
     const ST: u8 = 0b1000_0000;
     const H24_H12: u8 = 0b0100_0000;
     const AM_PM: u8 = 0b0010_0000;
+    const OSCRUN: u8 = 0b0010_0000;
+    const PWRFAIL: u8 = 0b0001_0000;
+    const VBATEN: u8 = 0b0000_1000;
+    const WEEKDAY: u8 = 0b0000_0111;
+    const LPYR: u8 = 0b0010_0000;
+    const ALMMSK: u8 = 0b0111_0000;
+    const ALMIF: u8 = 0b0000_1000;
+    const ALMPOL: u8 = 0b1000_0000;
+    const OUT: u8 = 0b1000_0000;
+    const SQWEN: u8 = 0b0100_0000;
+    const ALM1EN: u8 = 0b0010_0000;
+    const ALM0EN: u8 = 0b0001_0000;
+    const CRSTRIM: u8 = 0b0000_0100;
+    const SQWFS: u8 = 0b0000_0011;
+    const TRIM_SIGN: u8 = 0b1000_0000;
 }
 
 pub mod interface;
 use interface::I2cInterface;
+#[cfg(feature = "std")]
+use interface::TimedInterface;
 mod common;
+pub use common::alarm::alarm_registers_for;
+pub use common::datetime::{datetime_to_registers, registers_to_datetime};
+pub use common::sram::{SramRegion, RESERVED_SRAM_START};
+
+macro_rules! impl_create_destroy {
+    ($ic:ident, $create:ident, $create_with_convention:ident, $destroy:ident, $doc:expr) => {
+        impl<I2C, E> Mcp794xx<I2cInterface<I2C>, ic::$ic>
+        where
+            I2C: hal::blocking::i2c::Write<Error = E> + hal::blocking::i2c::WriteRead<Error = E>,
+        {
+            #[doc = $doc]
+            ///
+            /// The weekdays are numbered following ISO 8601 (1 = Monday).
+            pub fn $create(i2c: I2C) -> Self {
+                Self::$create_with_convention(i2c, WeekdayConvention::MondayFirst)
+            }
+
+            #[doc = $doc]
+            ///
+            /// The weekdays are numbered following `convention`. This is used
+            /// by the weekday helpers which do not take a convention
+            /// parameter.
+            pub fn $create_with_convention(i2c: I2C, convention: WeekdayConvention) -> Self {
+                Mcp794xx {
+                    iface: I2cInterface { i2c },
+                    is_enabled: false,
+                    century_base: 2000,
+                    weekday_convention: convention,
+                    valid_marker_address: None,
+                    _ic: PhantomData,
+                }
+            }
 
-impl<I2C, E> Mcp794xx<I2cInterface<I2C>>
+            /// Destroy driver instance, return I²C bus instance.
+            pub fn $destroy(self) -> I2C {
+                self.iface.i2c
+            }
+        }
+    };
+}
+
+impl_create_destroy!(
+    Mcp7940m,
+    new_mcp7940m,
+    new_mcp7940m_with_convention,
+    destroy_mcp7940m,
+    "Create a new instance of the MCP7940M device (without battery backup)."
+);
+impl_create_destroy!(
+    Mcp7940n,
+    new_mcp7940n,
+    new_mcp7940n_with_convention,
+    destroy_mcp7940n,
+    "Create a new instance of the MCP7940N device."
+);
+impl_create_destroy!(
+    Mcp79400,
+    new_mcp79400,
+    new_mcp79400_with_convention,
+    destroy_mcp79400,
+    "Create a new instance of the MCP79400 device (with protected EEPROM block)."
+);
+impl_create_destroy!(
+    Mcp79401,
+    new_mcp79401,
+    new_mcp79401_with_convention,
+    destroy_mcp79401,
+    "Create a new instance of the MCP79401 device (with EUI-48)."
+);
+impl_create_destroy!(
+    Mcp79402,
+    new_mcp79402,
+    new_mcp79402_with_convention,
+    destroy_mcp79402,
+    "Create a new instance of the MCP79402 device (with EUI-64)."
+);
+impl_create_destroy!(
+    Mcp79410,
+    new_mcp79410,
+    new_mcp79410_with_convention,
+    destroy_mcp79410,
+    "Create a new instance of the MCP79410 device (with EEPROM)."
+);
+impl_create_destroy!(
+    Mcp79411,
+    new_mcp79411,
+    new_mcp79411_with_convention,
+    destroy_mcp79411,
+    "Create a new instance of the MCP79411 device (with EEPROM and EUI-48)."
+);
+impl_create_destroy!(
+    Mcp79412,
+    new_mcp79412,
+    new_mcp79412_with_convention,
+    destroy_mcp79412,
+    "Create a new instance of the MCP79412 device (with EEPROM and EUI-64)."
+);
+
+impl<DI, IC> Mcp794xx<DI, IC> {
+    /// Mutable access to the underlying interface.
+    ///
+    /// This is intended for advanced use only, to perform transactions not
+    /// covered by the driver without having to destroy it.
+    /// The driver does not track changes made this way, so it is easy to
+    /// leave the device in a state inconsistent with what the driver
+    /// expects. For example, if the oscillator is stopped directly, later
+    /// calls to `set_seconds()` will start it again. Use with care.
+    pub fn iface_mut(&mut self) -> &mut DI {
+        &mut self.iface
+    }
+}
+
+impl<DI, IC: ic::Features> Mcp794xx<DI, IC> {
+    /// Whether the device has a user EEPROM.
+    ///
+    /// This is derived from the IC marker type so it can be used by code
+    /// shared between several device variants.
+    pub fn has_eeprom(&self) -> bool {
+        IC::HAS_EEPROM
+    }
+
+    /// Features of the device.
+    ///
+    /// This is derived from the IC marker type so that generic code can
+    /// adapt to the device variant at runtime.
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            battery_backup: IC::HAS_BATTERY_BACKUP,
+            eeprom: IC::HAS_EEPROM,
+            eui48: IC::HAS_EUI48,
+            eui64: IC::HAS_EUI64,
+            sram_size: IC::SRAM_SIZE,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<DI, IC> Mcp794xx<DI, IC> {
+    /// Wrap the interface in a [`TimedInterface`] so that the duration of
+    /// each transaction is recorded.
+    ///
+    /// [`TimedInterface`]: interface/struct.TimedInterface.html
+    pub fn into_timed(self) -> Mcp794xx<TimedInterface<DI>, IC> {
+        Mcp794xx {
+            iface: TimedInterface::new(self.iface),
+            is_enabled: self.is_enabled,
+            century_base: self.century_base,
+            weekday_convention: self.weekday_convention,
+            valid_marker_address: self.valid_marker_address,
+            _ic: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<DI, IC> Mcp794xx<TimedInterface<DI>, IC> {
+    /// Durations of the transactions performed so far, in order.
+    pub fn timings(&self) -> &[std::time::Duration] {
+        self.iface.timings()
+    }
+
+    /// Clear the recorded transaction durations.
+    pub fn clear_timings(&mut self) {
+        self.iface.clear_timings()
+    }
+
+    /// Remove the timing wrapper from the interface.
+    pub fn into_untimed(self) -> Mcp794xx<DI, IC> {
+        Mcp794xx {
+            iface: self.iface.destroy(),
+            is_enabled: self.is_enabled,
+            century_base: self.century_base,
+            weekday_convention: self.weekday_convention,
+            valid_marker_address: self.valid_marker_address,
+            _ic: PhantomData,
+        }
+    }
+}
+
+impl<DI, IC, E> Mcp794xx<DI, IC>
 where
-    I2C: hal::blocking::i2c::Write<Error = E> + hal::blocking::i2c::WriteRead<Error = E>,
+    DI: interface::WriteData<Error = Error<E>> + interface::ReadData<Error = Error<E>>,
+    IC: ic::Features,
 {
-    /// Create a new instance of the MCP7940N device.
-    pub fn new_mcp7940n(i2c: I2C) -> Self {
-        Mcp794xx {
-            iface: I2cInterface { i2c },
-            is_enabled: false,
+    /// Enable/disable the external battery supply (VBAT) used to keep the
+    /// clock and SRAM running when the main power is lost.
+    ///
+    /// Returns `Error::FeatureNotAvailable` on devices without battery
+    /// backup (MCP7940M).
+    pub fn set_vbat_en(&mut self, enable: bool) -> Result<(), Error<E>> {
+        if !IC::HAS_BATTERY_BACKUP {
+            return Err(Error::FeatureNotAvailable);
         }
+        self.modify_register(Register::WEEKDAY, BitFlags::VBATEN, enable)
     }
 
-    /// Destroy driver instance, return I²C bus instance.
-    pub fn destroy_mcp7940n(self) -> I2C {
-        self.iface.i2c
+    /// Enable/disable the external battery supply (VBAT) and read the
+    /// setting back to confirm it was applied.
+    ///
+    /// Returns `Error::VerificationFailed` if the read-back VBATEN bit does
+    /// not match the requested state.
+    pub fn set_vbat_en_verified(&mut self, enable: bool) -> Result<(), Error<E>> {
+        self.set_vbat_en(enable)?;
+        let weekday = self.iface.read_register(Register::WEEKDAY)?;
+        if (weekday & BitFlags::VBATEN != 0) != enable {
+            Err(Error::VerificationFailed)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Enable the external battery supply (VBAT) and confirm it was
+    /// applied by reading it back.
+    ///
+    /// Returns `Error::VerificationFailed` if VBATEN does not read back as
+    /// set. This only checks the configuration. To verify that the backup
+    /// supply actually works on a board:
+    /// 1. Clear the PWRFAIL flag (bit 4 of the RTCWKDAY register).
+    /// 2. With the clock running, remove and restore the main power.
+    /// 3. Check that PWRFAIL is now set and that the time kept running.
+    pub fn enable_battery_backup_verified(&mut self) -> Result<(), Error<E>> {
+        self.set_vbat_en_verified(true)
+    }
+
+    /// Enable the oscillator and set the external battery supply (VBAT)
+    /// as given, writing only what differs.
+    ///
+    /// The ST and VBATEN bits are read and each register is only written if
+    /// its bit does not match the desired state. Calling this again once the
+    /// device is configured only performs the two reads, so it can be used
+    /// as an idempotent initialization on every boot.
+    /// Returns `Error::FeatureNotAvailable` if `vbat` is `true` on devices
+    /// without battery backup (MCP7940M).
+    pub fn ensure_clock_configured(&mut self, vbat: bool) -> Result<(), Error<E>> {
+        if vbat && !IC::HAS_BATTERY_BACKUP {
+            return Err(Error::FeatureNotAvailable);
+        }
+        let seconds = self.iface.read_register(Register::SECONDS)?;
+        if (seconds & BitFlags::ST) == 0 {
+            self.iface
+                .write_register(Register::SECONDS, seconds | BitFlags::ST)?;
+        }
+        self.is_enabled = true;
+        let weekday = self.iface.read_register(Register::WEEKDAY)?;
+        if ((weekday & BitFlags::VBATEN) != 0) != vbat {
+            self.iface
+                .write_register(Register::WEEKDAY, weekday ^ BitFlags::VBATEN)?;
+        }
+        Ok(())
+    }
+}
+
+/// Estimate how many days a backup battery keeps the device running.
+///
+/// `capacity_mah` is the battery capacity in mAh and `backup_current_ua`
+/// the current drawn by the device from VBAT in µA, as given by the IBAT
+/// specification of the datasheet for the operating conditions. This is
+/// the capacity divided by the current, without taking self-discharge or
+/// temperature into account, so it is an upper bound for sizing the
+/// battery only.
+pub fn estimate_battery_life_days(capacity_mah: f32, backup_current_ua: f32) -> f32 {
+    capacity_mah * 1000.0 / backup_current_ua / 24.0
+}
+
+#[cfg(feature = "std")]
+impl<DI, IC, E> Mcp794xx<DI, IC>
+where
+    DI: interface::ReadData<Error = Error<E>>,
+{
+    /// Measure the duration of a single register read.
+    ///
+    /// The seconds register is read once and the time taken returned. This
+    /// is intended to profile the I2C setup. To record the duration of
+    /// every transaction, see [`into_timed()`](#method.into_timed).
+    pub fn measure_bus_latency(&mut self) -> Result<core::time::Duration, Error<E>> {
+        let start = std::time::Instant::now();
+        self.iface.read_register(Register::SECONDS)?;
+        Ok(start.elapsed())
     }
 }
-impl<DI, E> Mcp794xx<DI>
+
+impl<DI, IC, E> Mcp794xx<DI, IC>
 where
     DI: interface::WriteData<Error = Error<E>> + interface::ReadData<Error = Error<E>>,
 {
     /// Enable the oscillator (set the clock running).
     pub fn enable(&mut self) -> Result<(), Error<E>> {
-        let seconds = self.iface.read_register(Register::SECONDS)?;
-        self.iface
-            .write_register(Register::SECONDS, seconds | BitFlags::ST)?;
+        self.modify_register(Register::SECONDS, BitFlags::ST, true)?;
         self.is_enabled = true;
         Ok(())
     }
 
     /// Disable the oscillator (stops the clock) (default).
     pub fn disable(&mut self) -> Result<(), Error<E>> {
-        let seconds = self.iface.read_register(Register::SECONDS)?;
-        self.iface
-            .write_register(Register::SECONDS, seconds & !BitFlags::ST)?;
+        self.modify_register(Register::SECONDS, BitFlags::ST, false)?;
         self.is_enabled = false;
         Ok(())
     }
 
-    fn check_lt<T: PartialOrd>(value: T, reference: T) -> Result<(), Error<E>> {
-        if !(value < reference) {
-            Err(Error::InvalidInputData)
+    /// Enable the square-wave output on the MFP pin with the given frequency.
+    ///
+    /// The alarm enables and the other bits of the CONTROL register are
+    /// preserved.
+    pub fn enable_square_wave(&mut self, frequency: SqWaveFreq) -> Result<(), Error<E>> {
+        let control = self.iface.read_register(Register::CONTROL)?;
+        let control = (control & !BitFlags::SQWFS)
+            | BitFlags::SQWEN
+            | common::sq_wave_freq_to_register(frequency);
+        self.iface.write_register(Register::CONTROL, control)
+    }
+
+    /// Disable the square-wave output.
+    ///
+    /// The frequency selection and the other bits of the CONTROL register
+    /// are preserved.
+    pub fn disable_square_wave(&mut self) -> Result<(), Error<E>> {
+        self.modify_register(Register::CONTROL, BitFlags::SQWEN, false)
+    }
+
+    /// Set the digital trimming of the oscillator (OSCTRIM).
+    ///
+    /// Positive values add clock cycles to correct a slow clock and negative
+    /// values subtract them to correct a fast clock. Each step corresponds
+    /// to two clock cycles per minute. 0 disables the trimming.
+    /// Returns `Error::InvalidInputData` for -128, since the register holds
+    /// a sign bit and a 7-bit magnitude.
+    pub fn set_oscillator_trim(&mut self, value: i8) -> Result<(), Error<E>> {
+        if value == i8::MIN {
+            return Err(Error::InvalidInputData);
+        }
+        let data = if value > 0 {
+            BitFlags::TRIM_SIGN | value as u8
         } else {
-            Ok(())
+            (-value) as u8
+        };
+        self.iface.write_register(Register::OSCTRIM, data)
+    }
+
+    /// Read the digital trimming of the oscillator (OSCTRIM).
+    ///
+    /// See [`set_oscillator_trim()`](#method.set_oscillator_trim).
+    pub fn get_oscillator_trim(&mut self) -> Result<i8, Error<E>> {
+        let data = self.iface.read_register(Register::OSCTRIM)?;
+        let magnitude = (data & !BitFlags::TRIM_SIGN) as i8;
+        if (data & BitFlags::TRIM_SIGN) != 0 {
+            Ok(magnitude)
+        } else {
+            Ok(-magnitude)
         }
     }
+
+    /// Enable the coarse trim mode (CRSTRIM).
+    ///
+    /// The OSCTRIM value is then applied 128 times per second instead of
+    /// once per minute, which allows correcting larger crystal errors.
+    /// The other bits of the CONTROL register are preserved.
+    pub fn enable_coarse_trim(&mut self) -> Result<(), Error<E>> {
+        self.modify_register(Register::CONTROL, BitFlags::CRSTRIM, true)
+    }
+
+    /// Disable the coarse trim mode (CRSTRIM) (default).
+    ///
+    /// The other bits of the CONTROL register are preserved.
+    pub fn disable_coarse_trim(&mut self) -> Result<(), Error<E>> {
+        self.modify_register(Register::CONTROL, BitFlags::CRSTRIM, false)
+    }
+
+    /// Set the level of the MFP pin when used as a general purpose output
+    /// (OUT).
+    ///
+    /// This only has an effect on the pin while the square-wave output and
+    /// both alarms are disabled. Otherwise the level is stored and applied
+    /// once they are disabled. The other bits of the CONTROL register are
+    /// preserved.
+    pub fn set_output_pin(&mut self, high: bool) -> Result<(), Error<E>> {
+        self.modify_register(Register::CONTROL, BitFlags::OUT, high)
+    }
+
+    // Poll the OSCRUN bit for up to 1 s. Returns whether the oscillator runs.
+    fn wait_for_oscillator<D: hal::blocking::delay::DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<bool, Error<E>> {
+        for poll in 0..100 {
+            if poll != 0 {
+                delay.delay_ms(10);
+            }
+            let data = self.iface.read_register(Register::WEEKDAY)?;
+            if (data & BitFlags::OSCRUN) != 0 {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    // Read-modify-write of a register setting or clearing the bits in `mask`
+    fn modify_register(&mut self, register: u8, mask: u8, set: bool) -> Result<(), Error<E>> {
+        let data = self.iface.read_register(register)?;
+        let value = if set { data | mask } else { data & !mask };
+        self.iface.write_register(register, value)
+    }
+
+    fn check_lt<T: PartialOrd>(value: T, reference: T) -> Result<(), Error<E>> {
+        common::check_lt(value, reference)
+    }
+
+    fn check_between<T: PartialOrd>(value: T, lower: T, upper: T) -> Result<(), Error<E>> {
+        common::check_between(value, lower, upper)
+    }
 }
 
 mod private {
@@ -101,4 +883,36 @@ mod private {
     impl<E> Sealed for interface::I2cInterface<E> {}
     impl<E> Sealed for interface::ReadData<Error = E> {}
     impl<E> Sealed for interface::WriteData<Error = E> {}
+    impl<E> Sealed for interface::EepromData<Error = E> {}
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate embedded_hal_mock;
+    use self::embedded_hal_mock::i2c::{Mock as I2cMock, Transaction as I2cTrans};
+    use super::*;
+
+    #[test]
+    fn can_modify_register() {
+        let trans = [
+            I2cTrans::write_read(
+                DEVICE_ADDRESS,
+                [Register::WEEKDAY].to_vec(),
+                [0b1010_0101].to_vec(),
+            ),
+            I2cTrans::write(DEVICE_ADDRESS, [Register::WEEKDAY, 0b1010_1101].to_vec()),
+            I2cTrans::write_read(
+                DEVICE_ADDRESS,
+                [Register::WEEKDAY].to_vec(),
+                [0b1010_1101].to_vec(),
+            ),
+            I2cTrans::write(DEVICE_ADDRESS, [Register::WEEKDAY, 0b1010_0101].to_vec()),
+        ];
+        let mut dev = Mcp794xx::new_mcp7940n(I2cMock::new(&trans));
+        dev.modify_register(Register::WEEKDAY, BitFlags::VBATEN, true)
+            .unwrap();
+        dev.modify_register(Register::WEEKDAY, BitFlags::VBATEN, false)
+            .unwrap();
+        dev.destroy_mcp7940n().done();
+    }
 }
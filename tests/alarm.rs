@@ -0,0 +1,1077 @@
+extern crate embedded_hal_mock as hal;
+use hal::i2c::Transaction as I2cTrans;
+mod common;
+use common::{
+    destroy_mcp79400, destroy_mcp79401, destroy_mcp79402, destroy_mcp7940m, destroy_mcp7940n,
+    destroy_mcp79410, destroy_mcp79411, destroy_mcp79412, new_mcp79400, new_mcp79401, new_mcp79402,
+    new_mcp7940m, new_mcp7940n, new_mcp79410, new_mcp79411, new_mcp79412, BitFlags, Register,
+    DEVICE_ADDRESS as DEV_ADDR,
+};
+extern crate mcp794xx;
+use mcp794xx::{Alarm, AlarmDateTime, AlarmMatching, Error, Hours, OutputPolarity};
+
+macro_rules! read_raw_test {
+    ($name:ident, $create_method:ident, $destroy_method:ident,
+    $method:ident, $register:ident, [ $( $value:expr ),+ ]) => {
+        #[test]
+        fn $name() {
+            let trans = [I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::$register],
+                vec![$( $value ),*],
+            )];
+            let mut dev = $create_method(&trans);
+            let mut buf = [0; 6];
+            dev.$method(&mut buf).unwrap();
+            assert_eq!([$( $value ),*], buf);
+            $destroy_method(dev);
+        }
+    };
+}
+
+for_all_ics!(
+    can_read_alarm1_raw,
+    read_raw_test,
+    read_alarm1_raw,
+    ALM1SEC,
+    [0x12, 0x34, 0x56, 0x07, 0x28, 0x11]
+);
+
+mod alarm0_mask {
+    use super::*;
+    // The polarity, interrupt flag and weekday bits are ignored
+    get_param_test!(
+        seconds,
+        get_alarm0_mask,
+        ALM0WKDAY,
+        AlarmMatching::SecondsMatch,
+        [0b1000_1111]
+    );
+    get_param_test!(
+        minutes,
+        get_alarm0_mask,
+        ALM0WKDAY,
+        AlarmMatching::MinutesMatch,
+        [0b0001_0001]
+    );
+    get_param_test!(
+        hours,
+        get_alarm0_mask,
+        ALM0WKDAY,
+        AlarmMatching::HoursMatch,
+        [0b0010_0001]
+    );
+    get_param_test!(
+        weekday,
+        get_alarm0_mask,
+        ALM0WKDAY,
+        AlarmMatching::WeekdayMatch,
+        [0b0011_0001]
+    );
+    get_param_test!(
+        day,
+        get_alarm0_mask,
+        ALM0WKDAY,
+        AlarmMatching::DayMatch,
+        [0b0100_0001]
+    );
+    get_param_test!(
+        all,
+        get_alarm0_mask,
+        ALM0WKDAY,
+        AlarmMatching::AllMatch,
+        [0b1111_1001]
+    );
+
+    #[test]
+    fn reserved_value_is_rejected() {
+        let trans = [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::ALM0WKDAY],
+            vec![0b0101_0001],
+        )];
+        let mut dev = new_mcp7940n(&trans);
+        assert_invalid_input_data!(dev.get_alarm0_mask());
+        destroy_mcp7940n(dev);
+    }
+}
+
+macro_rules! format_matches_test {
+    ($name:ident, $alarm:ident, $register:ident, $clock:expr, $alarm_hours:expr, $expected:expr) => {
+        for_all_ics!(
+            $name,
+            get_test,
+            alarm_format_matches_clock,
+            [
+                I2cTrans::write_read(DEV_ADDR, vec![Register::HOURS], vec![$clock]),
+                I2cTrans::write_read(DEV_ADDR, vec![Register::$register], vec![$alarm_hours])
+            ],
+            $expected,
+            (Alarm::$alarm)
+        );
+    };
+}
+
+mod format_matches {
+    use super::*;
+    format_matches_test!(alarm0_both_24h, Zero, ALM0HOUR, 0x23, 0x12, true);
+    format_matches_test!(
+        alarm0_both_12h,
+        Zero,
+        ALM0HOUR,
+        BitFlags::H24_H12 | 0x11,
+        BitFlags::H24_H12 | 0x01,
+        true
+    );
+    format_matches_test!(
+        alarm0_mismatch,
+        Zero,
+        ALM0HOUR,
+        BitFlags::H24_H12 | 0x11,
+        0x13,
+        false
+    );
+    format_matches_test!(alarm1_both_24h, One, ALM1HOUR, 0x23, 0x12, true);
+    format_matches_test!(
+        alarm1_mismatch,
+        One,
+        ALM1HOUR,
+        0x23,
+        BitFlags::H24_H12 | 0x11,
+        false
+    );
+}
+
+mod harmonize_formats {
+    use super::*;
+
+    for_all_ics!(
+        nothing_to_fix,
+        get_test,
+        harmonize_formats,
+        [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::HOURS], vec![0x23]),
+            I2cTrans::write_read(DEV_ADDR, vec![Register::ALM0HOUR], vec![0x12]),
+            I2cTrans::write_read(DEV_ADDR, vec![Register::ALM1HOUR], vec![0x07])
+        ],
+        false
+    );
+
+    for_all_ics!(
+        fixes_alarm0_to_12h,
+        get_test,
+        harmonize_formats,
+        [
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::HOURS],
+                vec![BitFlags::H24_H12 | 0x11]
+            ),
+            I2cTrans::write_read(DEV_ADDR, vec![Register::ALM0HOUR], vec![0x13]),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![
+                    Register::ALM0HOUR,
+                    BitFlags::H24_H12 | BitFlags::AM_PM | 0x01
+                ]
+            ),
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::ALM1HOUR],
+                vec![BitFlags::H24_H12 | 0x12]
+            )
+        ],
+        true
+    );
+
+    for_all_ics!(
+        fixes_both_to_24h,
+        get_test,
+        harmonize_formats,
+        [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::HOURS], vec![0x08]),
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::ALM0HOUR],
+                vec![BitFlags::H24_H12 | BitFlags::AM_PM | 0x11]
+            ),
+            I2cTrans::write(DEV_ADDR, vec![Register::ALM0HOUR, 0x23]),
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::ALM1HOUR],
+                vec![BitFlags::H24_H12 | 0x12]
+            ),
+            I2cTrans::write(DEV_ADDR, vec![Register::ALM1HOUR, 0x00])
+        ],
+        true
+    );
+}
+
+macro_rules! alarms_configured_test {
+    ($name:ident, $alarm0:expr, $alarm1:expr, $expected:expr) => {
+        for_all_ics!(
+            $name,
+            get_test,
+            alarms_are_configured,
+            [
+                I2cTrans::write_read(DEV_ADDR, vec![Register::ALM0SEC], $alarm0.to_vec()),
+                I2cTrans::write_read(DEV_ADDR, vec![Register::ALM1SEC], $alarm1.to_vec())
+            ],
+            $expected
+        );
+    };
+}
+
+mod alarms_configured {
+    use super::*;
+    alarms_configured_test!(default, [0; 6], [0; 6], false);
+    alarms_configured_test!(
+        only_flags_set,
+        [0, 0, 0, BitFlags::ALMIF, 0, 0],
+        [0, 0, 0, BitFlags::ALMIF, 0, 0],
+        false
+    );
+    alarms_configured_test!(
+        alarm0_configured,
+        [0x30, 0x15, 0x07, 0x71, 0x01, 0x01],
+        [0; 6],
+        true
+    );
+    alarms_configured_test!(alarm1_configured, [0; 6], [0, 0, 0, 0, 0x15, 0], true);
+}
+
+mod emulated_daily {
+    use super::*;
+
+    // Monday 2019-03-04 23:30:00
+    fn read_datetime() -> I2cTrans {
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::SECONDS],
+            vec![
+                BitFlags::ST,
+                0x30,
+                0x23,
+                BitFlags::VBATEN | 1,
+                0x04,
+                0x03,
+                0x19,
+            ],
+        )
+    }
+
+    fn read_control() -> I2cTrans {
+        I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![0])
+    }
+
+    fn enable_alarm0() -> I2cTrans {
+        I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, BitFlags::ALM0EN])
+    }
+
+    macro_rules! set_emulated_test {
+        ($name:ident, $alarm_wkday:expr, [ $( $write_bin:expr ),+ ], $( $value:expr ),+) => {
+            for_all_ics!(
+                $name,
+                call_test,
+                set_emulated_daily_alarm0,
+                [
+                    read_datetime(),
+                    I2cTrans::write_read(DEV_ADDR, vec![Register::ALM0WKDAY], vec![$alarm_wkday]),
+                    I2cTrans::write(DEV_ADDR, vec![Register::ALM0SEC, $( $write_bin ),*]),
+                    read_control(),
+                    enable_alarm0()
+                ],
+                $( $value ),*
+            );
+        };
+    }
+
+    set_emulated_test!(
+        can_set_today,
+        0,
+        [0x45, 0x59, 0x23, BitFlags::ALMMSK | 1, 0x04, 0x03],
+        (Hours::H24(23)),
+        59,
+        45
+    );
+
+    set_emulated_test!(
+        can_set_tomorrow,
+        0,
+        [0x15, 0x00, 0x07, BitFlags::ALMMSK | 2, 0x05, 0x03],
+        (Hours::H24(7)),
+        0,
+        15
+    );
+
+    set_emulated_test!(
+        keeps_polarity_and_clears_flag,
+        BitFlags::ALMPOL | BitFlags::ALMIF | 0b0101_0001,
+        [
+            0x00,
+            0x30,
+            0x23,
+            BitFlags::ALMPOL | BitFlags::ALMMSK | 2,
+            0x05,
+            0x03
+        ],
+        (Hours::H24(23)),
+        30,
+        0
+    );
+
+    for_all_ics!(
+        can_reschedule,
+        call_test,
+        reschedule_emulated_daily_alarm0,
+        [
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::ALM0SEC],
+                vec![
+                    0x00,
+                    0x30,
+                    0x23,
+                    BitFlags::ALMMSK | BitFlags::ALMIF | 1,
+                    0x04,
+                    0x03
+                ]
+            ),
+            read_datetime(),
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::ALM0WKDAY],
+                vec![BitFlags::ALMMSK | BitFlags::ALMIF | 1]
+            ),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![
+                    Register::ALM0SEC,
+                    0x00,
+                    0x30,
+                    0x23,
+                    BitFlags::ALMMSK | 2,
+                    0x05,
+                    0x03
+                ]
+            ),
+            read_control(),
+            enable_alarm0()
+        ]
+    );
+
+    // Monday 2019-03-04 11:30:00 PM with the clock in 12h mode
+    for_all_ics!(
+        converts_hour_to_clock_format,
+        call_test,
+        set_emulated_daily_alarm0,
+        [
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::SECONDS],
+                vec![
+                    BitFlags::ST,
+                    0x30,
+                    BitFlags::H24_H12 | BitFlags::AM_PM | 0x11,
+                    BitFlags::VBATEN | 1,
+                    0x04,
+                    0x03,
+                    0x19
+                ]
+            ),
+            I2cTrans::write_read(DEV_ADDR, vec![Register::ALM0WKDAY], vec![0]),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![
+                    Register::ALM0SEC,
+                    0x15,
+                    0x00,
+                    BitFlags::H24_H12 | 0x07,
+                    BitFlags::ALMMSK | 2,
+                    0x05,
+                    0x03
+                ]
+            ),
+            read_control(),
+            enable_alarm0()
+        ],
+        (Hours::H24(7)),
+        0,
+        15
+    );
+
+    for_all_ics!(
+        cannot_set_invalid_hour,
+        set_invalid_test,
+        set_emulated_daily_alarm0,
+        (Hours::H24(24)),
+        0,
+        0
+    );
+    for_all_ics!(
+        cannot_set_invalid_second,
+        set_invalid_test,
+        set_emulated_daily_alarm0,
+        (Hours::H24(7)),
+        0,
+        60
+    );
+}
+
+for_all_ics!(
+    can_swap_alarms,
+    call_test,
+    swap_alarms,
+    [
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::ALM0SEC],
+            vec![
+                0x15,
+                0x30,
+                0x07,
+                BitFlags::ALMPOL | BitFlags::ALMMSK | 2,
+                0x05,
+                0x03
+            ]
+        ),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::ALM1SEC],
+            vec![0x00, 0x45, 0x21, BitFlags::ALMIF | 0b0010_0001, 0x04, 0x03]
+        ),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![
+                Register::ALM0SEC,
+                0x00,
+                0x45,
+                0x21,
+                BitFlags::ALMPOL | BitFlags::ALMIF | 0b0010_0001,
+                0x04,
+                0x03
+            ]
+        ),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![
+                Register::ALM1SEC,
+                0x15,
+                0x30,
+                0x07,
+                BitFlags::ALMMSK | 2,
+                0x05,
+                0x03
+            ]
+        )
+    ]
+);
+
+for_all_ics!(
+    can_reset_alarms,
+    call_test,
+    reset_alarms,
+    [
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::CONTROL],
+            vec![BitFlags::OUT | BitFlags::ALM0EN | BitFlags::ALM1EN | 0b11]
+        ),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, BitFlags::OUT | 0b11]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ALM0SEC, 0, 0, 0, 0, 0, 0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ALM1SEC, 0, 0, 0, 0, 0, 0])
+    ]
+);
+
+macro_rules! get_polarity_test {
+    ($name:ident, $value:expr, $expected:ident) => {
+        for_all_ics!(
+            $name,
+            get_test,
+            get_alarm_polarity,
+            [I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::ALM0WKDAY],
+                vec![$value]
+            )],
+            (OutputPolarity::$expected)
+        );
+    };
+}
+
+get_polarity_test!(
+    can_get_polarity_low,
+    BitFlags::ALMMSK | BitFlags::ALMIF | 7,
+    Low
+);
+get_polarity_test!(can_get_polarity_high, BitFlags::ALMPOL | 1, High);
+
+macro_rules! set_polarity_test {
+    ($name:ident, $polarity:ident, $current:expr, $expected:expr) => {
+        for_all_ics!(
+            $name,
+            call_test,
+            set_alarm_output_polarity,
+            [
+                I2cTrans::write_read(DEV_ADDR, vec![Register::ALM0WKDAY], vec![$current]),
+                I2cTrans::write(DEV_ADDR, vec![Register::ALM0WKDAY, $expected])
+            ],
+            (OutputPolarity::$polarity)
+        );
+    };
+}
+
+// The weekday, matching configuration and interrupt flag are kept.
+set_polarity_test!(
+    can_set_polarity_high,
+    High,
+    BitFlags::ALMMSK | BitFlags::ALMIF | 7,
+    BitFlags::ALMPOL | BitFlags::ALMMSK | BitFlags::ALMIF | 7
+);
+set_polarity_test!(
+    can_set_polarity_low,
+    Low,
+    BitFlags::ALMPOL | 0b0100_0011,
+    0b0100_0011
+);
+
+fn new_alarm_datetime(hour: Hours) -> AlarmDateTime {
+    AlarmDateTime {
+        month: 12,
+        day: 31,
+        weekday: 2,
+        hour,
+        minute: 59,
+        second: 58,
+    }
+}
+
+macro_rules! set_alarm_test {
+    ($name:ident, $method:ident, $register:ident, $wkday_register:ident, $current_wkday:expr,
+    $matching:ident, $hour:expr, $wkday:expr) => {
+        for_all_ics!(
+            $name,
+            call_test,
+            $method,
+            [
+                I2cTrans::write_read(
+                    DEV_ADDR,
+                    vec![Register::$wkday_register],
+                    vec![$current_wkday]
+                ),
+                I2cTrans::write(
+                    DEV_ADDR,
+                    vec![Register::$register, 0x58, 0x59, $hour, $wkday, 0x31, 0x12]
+                )
+            ],
+            (&new_alarm_datetime(Hours::H24(23))),
+            (AlarmMatching::$matching)
+        );
+    };
+}
+
+mod alarm0 {
+    use super::*;
+
+    macro_rules! set_alarm0_test {
+        ($name:ident, $current_wkday:expr, $matching:ident, $wkday:expr) => {
+            set_alarm_test!(
+                $name,
+                set_alarm0,
+                ALM0SEC,
+                ALM0WKDAY,
+                $current_wkday,
+                $matching,
+                0x23,
+                $wkday
+            );
+        };
+    }
+
+    set_alarm0_test!(set_seconds_match, 0, SecondsMatch, 2);
+    set_alarm0_test!(set_minutes_match, 0, MinutesMatch, 0b0001_0010);
+    set_alarm0_test!(set_hours_match, 0, HoursMatch, 0b0010_0010);
+    set_alarm0_test!(set_weekday_match, 0, WeekdayMatch, 0b0011_0010);
+    set_alarm0_test!(set_day_match, 0, DayMatch, 0b0100_0010);
+    set_alarm0_test!(set_all_match, 0, AllMatch, 0b0111_0010);
+    set_alarm0_test!(
+        set_keeps_polarity_and_clears_flag,
+        BitFlags::ALMPOL | BitFlags::ALMIF | 0b0011_0101,
+        DayMatch,
+        BitFlags::ALMPOL | 0b0100_0010
+    );
+
+    for_all_ics!(
+        set_12h,
+        call_test,
+        set_alarm0,
+        [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::ALM0WKDAY], vec![0]),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![
+                    Register::ALM0SEC,
+                    0x58,
+                    0x59,
+                    BitFlags::H24_H12 | BitFlags::AM_PM | 0x11,
+                    0b0111_0010,
+                    0x31,
+                    0x12
+                ]
+            )
+        ],
+        (&new_alarm_datetime(Hours::PM(11))),
+        (AlarmMatching::AllMatch)
+    );
+
+    for_all_ics!(
+        get,
+        get_test,
+        get_alarm0,
+        [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::ALM0SEC],
+            vec![
+                0x58,
+                0x59,
+                0x23,
+                BitFlags::ALMPOL | BitFlags::ALMIF | 0b0100_0010,
+                0x31,
+                0x12
+            ]
+        )],
+        (new_alarm_datetime(Hours::H24(23)), AlarmMatching::DayMatch)
+    );
+
+    #[test]
+    fn cannot_get_reserved_matching() {
+        let trans = [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::ALM0SEC],
+            vec![0x58, 0x59, 0x23, 0b0101_0010, 0x31, 0x12],
+        )];
+        let mut dev = new_mcp7940n(&trans);
+        assert_invalid_input_data!(dev.get_alarm0());
+        destroy_mcp7940n(dev);
+    }
+
+    macro_rules! set_invalid_alarm0_test {
+        ($name:ident, $field:ident, $value:expr) => {
+            for_all_ics!(
+                $name,
+                set_invalid_test,
+                set_alarm0,
+                (&AlarmDateTime {
+                    $field: $value,
+                    ..new_alarm_datetime(Hours::H24(23))
+                }),
+                (AlarmMatching::AllMatch)
+            );
+        };
+    }
+
+    set_invalid_alarm0_test!(invalid_second, second, 60);
+    set_invalid_alarm0_test!(invalid_minute, minute, 60);
+    set_invalid_alarm0_test!(invalid_hour, hour, Hours::H24(25));
+    set_invalid_alarm0_test!(invalid_hour_12h, hour, Hours::AM(13));
+    set_invalid_alarm0_test!(invalid_weekday, weekday, 0);
+    set_invalid_alarm0_test!(invalid_day, day, 32);
+    set_invalid_alarm0_test!(invalid_month, month, 13);
+}
+
+mod alarm1 {
+    use super::*;
+
+    macro_rules! set_alarm1_test {
+        ($name:ident, $matching:ident, $wkday:expr) => {
+            for_all_ics!(
+                $name,
+                call_test,
+                set_alarm1,
+                [I2cTrans::write(
+                    DEV_ADDR,
+                    vec![Register::ALM1SEC, 0x58, 0x59, 0x23, $wkday, 0x31, 0x12]
+                )],
+                (&new_alarm_datetime(Hours::H24(23))),
+                (AlarmMatching::$matching)
+            );
+        };
+    }
+
+    // Only the alarm 1 registers are accessed.
+    set_alarm1_test!(set_seconds_match, SecondsMatch, 2);
+    set_alarm1_test!(set_hours_match, HoursMatch, 0b0010_0010);
+    set_alarm1_test!(set_all_match, AllMatch, 0b0111_0010);
+
+    for_all_ics!(
+        get,
+        get_test,
+        get_alarm1,
+        [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::ALM1SEC],
+            vec![0x58, 0x59, 0x23, BitFlags::ALMIF | 0b0011_0010, 0x31, 0x12]
+        )],
+        (
+            new_alarm_datetime(Hours::H24(23)),
+            AlarmMatching::WeekdayMatch
+        )
+    );
+
+    for_all_ics!(
+        cannot_set_invalid_hour,
+        set_invalid_test,
+        set_alarm1,
+        (&new_alarm_datetime(Hours::H24(25))),
+        (AlarmMatching::AllMatch)
+    );
+}
+
+macro_rules! alarm_enable_test {
+    ($name:ident, $method:ident, $alarm:ident, $initial:expr, $expected:expr) => {
+        for_all_ics!(
+            $name,
+            call_test,
+            $method,
+            [
+                I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![$initial]),
+                I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, $expected])
+            ],
+            (Alarm::$alarm)
+        );
+    };
+}
+
+alarm_enable_test!(
+    can_enable_alarm0,
+    enable_alarm,
+    Zero,
+    BitFlags::OUT | BitFlags::ALM1EN | 0b11,
+    BitFlags::OUT | BitFlags::ALM1EN | BitFlags::ALM0EN | 0b11
+);
+alarm_enable_test!(
+    can_enable_alarm1,
+    enable_alarm,
+    One,
+    BitFlags::OUT,
+    BitFlags::OUT | BitFlags::ALM1EN
+);
+alarm_enable_test!(
+    can_disable_alarm0,
+    disable_alarm,
+    Zero,
+    BitFlags::SQWEN | BitFlags::ALM1EN | BitFlags::ALM0EN,
+    BitFlags::SQWEN | BitFlags::ALM1EN
+);
+alarm_enable_test!(
+    can_disable_alarm1,
+    disable_alarm,
+    One,
+    BitFlags::OUT | BitFlags::ALM1EN | BitFlags::ALM0EN,
+    BitFlags::OUT | BitFlags::ALM0EN
+);
+
+mod mirror_alarm0_to_alarm1 {
+    use super::*;
+
+    macro_rules! mirror_test {
+        ($name:ident, $alarm0:expr, $year:expr, $offset:expr, $alarm1:expr) => {
+            for_all_ics!(
+                $name,
+                call_test,
+                mirror_alarm0_to_alarm1_with_offset,
+                [
+                    I2cTrans::write_read(DEV_ADDR, vec![Register::ALM0SEC], $alarm0),
+                    I2cTrans::write_read(DEV_ADDR, vec![Register::YEAR], vec![$year]),
+                    I2cTrans::write(DEV_ADDR, $alarm1)
+                ],
+                $offset
+            );
+        };
+    }
+
+    mirror_test!(
+        same_day,
+        vec![0x00, 0x30, 0x07, BitFlags::ALMPOL | 0b0011_0001, 0x04, 0x03],
+        0x19,
+        90,
+        vec![Register::ALM1SEC, 0x30, 0x31, 0x07, 0b0011_0001, 0x04, 0x03]
+    );
+
+    mirror_test!(
+        crosses_year_boundary,
+        vec![0x58, 0x59, 0x23, BitFlags::ALMIF | 0b0111_0010, 0x31, 0x12],
+        0x18,
+        5,
+        vec![Register::ALM1SEC, 0x03, 0x00, 0x00, 0b0111_0011, 0x01, 0x01]
+    );
+
+    mirror_test!(
+        keeps_12h_format_across_leap_day,
+        vec![0x00, 0x59, 0b0111_0001, 0b0111_0011, 0x28, 0x02],
+        0x20,
+        60,
+        vec![
+            Register::ALM1SEC,
+            0x00,
+            0x00,
+            0b0101_0010,
+            0b0111_0100,
+            0x29,
+            0x02
+        ]
+    );
+}
+
+mod alarm_matched {
+    use super::*;
+
+    macro_rules! has_matched_test {
+        ($name:ident, $alarm:ident, $register:ident, $value:expr, $expected:expr) => {
+            for_all_ics!(
+                $name,
+                get_test,
+                has_alarm_matched,
+                [I2cTrans::write_read(
+                    DEV_ADDR,
+                    vec![Register::$register],
+                    vec![$value]
+                )],
+                $expected,
+                (Alarm::$alarm)
+            );
+        };
+    }
+
+    has_matched_test!(
+        alarm0_matched,
+        Zero,
+        ALM0WKDAY,
+        BitFlags::ALMIF | 0x71,
+        true
+    );
+    has_matched_test!(alarm0_not_matched, Zero, ALM0WKDAY, 0xF7, false);
+    has_matched_test!(alarm1_matched, One, ALM1WKDAY, BitFlags::ALMIF, true);
+    has_matched_test!(alarm1_not_matched, One, ALM1WKDAY, 0x77, false);
+
+    macro_rules! clear_test {
+        ($name:ident, $alarm:ident, $register:ident, $value:expr) => {
+            for_all_ics!(
+                $name,
+                call_test,
+                clear_alarm_matched_flag,
+                [
+                    I2cTrans::write_read(DEV_ADDR, vec![Register::$register], vec![$value]),
+                    I2cTrans::write(
+                        DEV_ADDR,
+                        vec![Register::$register, $value & !BitFlags::ALMIF]
+                    )
+                ],
+                (Alarm::$alarm)
+            );
+        };
+    }
+
+    // Only bit 3 is cleared; polarity, mask and weekday are preserved.
+    clear_test!(
+        clear_alarm0,
+        Zero,
+        ALM0WKDAY,
+        BitFlags::ALMPOL | BitFlags::ALMIF | 0b0111_0101
+    );
+    clear_test!(clear_alarm1, One, ALM1WKDAY, BitFlags::ALMIF | 0b0100_0011);
+}
+
+mod alarm0_all_fields {
+    use super::*;
+    use mcp794xx::DateTime;
+
+    fn new_datetime(hour: Hours) -> DateTime {
+        DateTime {
+            year: 2019,
+            month: 12,
+            day: 31,
+            weekday: 2,
+            hour,
+            minute: 59,
+            second: 58,
+        }
+    }
+
+    macro_rules! all_fields_test {
+        ($name:ident, $hour:expr, $clock_hours:expr, $alarm_hours:expr) => {
+            for_all_ics!(
+                $name,
+                call_test,
+                set_alarm0_all_fields,
+                [
+                    I2cTrans::write_read(DEV_ADDR, vec![Register::HOURS], vec![$clock_hours]),
+                    I2cTrans::write_read(
+                        DEV_ADDR,
+                        vec![Register::ALM0WKDAY],
+                        vec![BitFlags::ALMPOL | BitFlags::ALMIF | 0b0001_0101]
+                    ),
+                    I2cTrans::write(
+                        DEV_ADDR,
+                        vec![
+                            Register::ALM0SEC,
+                            0x58,
+                            0x59,
+                            $alarm_hours,
+                            BitFlags::ALMPOL | 0b0111_0010,
+                            0x31,
+                            0x12
+                        ]
+                    )
+                ],
+                (&new_datetime($hour))
+            );
+        };
+    }
+
+    all_fields_test!(h24_clock, Hours::H24(23), 0x12, 0x23);
+    all_fields_test!(h24_to_h12_clock, Hours::H24(23), 0b0100_0001, 0b0111_0001);
+    all_fields_test!(h12_to_h24_clock, Hours::PM(11), 0x05, 0x23);
+
+    for_all_ics!(
+        cannot_set_invalid_weekday,
+        set_invalid_test,
+        set_alarm0_all_fields,
+        (&DateTime {
+            weekday: 8,
+            ..new_datetime(Hours::H24(23))
+        })
+    );
+
+    for_all_ics!(
+        cannot_set_invalid_year,
+        set_invalid_test,
+        set_alarm0_all_fields,
+        (&DateTime {
+            year: 2100,
+            ..new_datetime(Hours::H24(23))
+        })
+    );
+}
+
+mod alarm_registers_for {
+    use super::*;
+    use mcp794xx::{alarm_registers_for, DateTime};
+
+    fn new_datetime(hour: Hours) -> DateTime {
+        DateTime {
+            year: 2019,
+            month: 12,
+            day: 31,
+            weekday: 2,
+            hour,
+            minute: 59,
+            second: 58,
+        }
+    }
+
+    #[test]
+    fn matches_set_alarm0() {
+        let registers =
+            alarm_registers_for::<()>(&new_datetime(Hours::PM(11)), AlarmMatching::AllMatch)
+                .unwrap();
+        let trans = [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::ALM0WKDAY], vec![0]),
+            I2cTrans::write(DEV_ADDR, [&[Register::ALM0SEC][..], &registers].concat()),
+        ];
+        let mut dev = new_mcp7940n(&trans);
+        dev.set_alarm0(&new_alarm_datetime(Hours::PM(11)), AlarmMatching::AllMatch)
+            .unwrap();
+        destroy_mcp7940n(dev);
+    }
+
+    #[test]
+    fn can_encode() {
+        assert_eq!(
+            [0x58, 0x59, 0x23, 0b0100_0010, 0x31, 0x12],
+            alarm_registers_for::<()>(&new_datetime(Hours::H24(23)), AlarmMatching::DayMatch)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn cannot_encode_invalid() {
+        let datetime = DateTime {
+            minute: 60,
+            ..new_datetime(Hours::H24(23))
+        };
+        assert_invalid_input_data!(alarm_registers_for::<()>(
+            &datetime,
+            AlarmMatching::AllMatch
+        ));
+    }
+}
+
+mod setup_alarm {
+    use super::*;
+
+    for_all_ics!(
+        can_setup_alarm0,
+        call_test,
+        setup_alarm,
+        [
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::ALM0WKDAY],
+                vec![BitFlags::ALMPOL | BitFlags::ALMIF | 0b0011_0101]
+            ),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![
+                    Register::ALM0SEC,
+                    0x58,
+                    0x59,
+                    0x23,
+                    BitFlags::ALMPOL | 0b0100_0010,
+                    0x31,
+                    0x12
+                ]
+            ),
+            I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![BitFlags::OUT]),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![Register::CONTROL, BitFlags::OUT | BitFlags::ALM0EN]
+            )
+        ],
+        (Alarm::Zero),
+        (&new_alarm_datetime(Hours::H24(23))),
+        (AlarmMatching::DayMatch)
+    );
+
+    for_all_ics!(
+        can_setup_alarm1,
+        call_test,
+        setup_alarm,
+        [
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![Register::ALM1SEC, 0x58, 0x59, 0x23, 0b0111_0010, 0x31, 0x12]
+            ),
+            I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![BitFlags::ALM0EN]),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![Register::CONTROL, BitFlags::ALM0EN | BitFlags::ALM1EN]
+            )
+        ],
+        (Alarm::One),
+        (&new_alarm_datetime(Hours::H24(23))),
+        (AlarmMatching::AllMatch)
+    );
+
+    for_all_ics!(
+        cannot_setup_invalid,
+        set_invalid_test,
+        setup_alarm,
+        (Alarm::Zero),
+        (&AlarmDateTime {
+            second: 60,
+            ..new_alarm_datetime(Hours::H24(23))
+        }),
+        (AlarmMatching::AllMatch)
+    );
+}
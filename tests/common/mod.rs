@@ -1,11 +1,13 @@
 extern crate embedded_hal;
 extern crate mcp794xx;
-use self::mcp794xx::{interface, Mcp794xx};
+use self::mcp794xx::{ic, interface, Mcp794xx};
 extern crate embedded_hal_mock as hal;
 use self::hal::i2c::{Mock as I2cMock, Transaction as I2cTrans};
 
 #[allow(unused)]
 pub const DEVICE_ADDRESS: u8 = 0b1101111;
+#[allow(unused)]
+pub const EEPROM_ADDRESS: u8 = 0b1010111;
 pub struct Register;
 
 #[allow(unused)]
@@ -13,6 +15,22 @@ impl Register {
     pub const SECONDS: u8 = 0x00;
     pub const MINUTES: u8 = 0x01;
     pub const HOURS: u8 = 0x02;
+    pub const WEEKDAY: u8 = 0x03;
+    pub const DAY: u8 = 0x04;
+    pub const MONTH: u8 = 0x05;
+    pub const YEAR: u8 = 0x06;
+    pub const CONTROL: u8 = 0x07;
+    pub const OSCTRIM: u8 = 0x08;
+    pub const EEUNLOCK: u8 = 0x09;
+    pub const ALM0SEC: u8 = 0x0A;
+    pub const ALM0HOUR: u8 = 0x0C;
+    pub const ALM0WKDAY: u8 = 0x0D;
+    pub const ALM1SEC: u8 = 0x11;
+    pub const ALM1HOUR: u8 = 0x13;
+    pub const ALM1WKDAY: u8 = 0x14;
+    pub const PWRDNMIN: u8 = 0x18;
+    pub const PWRUPMIN: u8 = 0x1C;
+    pub const SRAM: u8 = 0x20;
 }
 
 pub struct BitFlags;
@@ -20,16 +38,48 @@ pub struct BitFlags;
 #[allow(unused)]
 impl BitFlags {
     pub const ST: u8 = 0b1000_0000;
+    pub const H24_H12: u8 = 0b0100_0000;
+    pub const AM_PM: u8 = 0b0010_0000;
+    pub const ALMIF: u8 = 0b0000_1000;
+    pub const PWRFAIL: u8 = 0b0001_0000;
+    pub const VBATEN: u8 = 0b0000_1000;
+    pub const LPYR: u8 = 0b0010_0000;
+    pub const OSCRUN: u8 = 0b0010_0000;
+    pub const OUT: u8 = 0b1000_0000;
+    pub const SQWEN: u8 = 0b0100_0000;
+    pub const ALM1EN: u8 = 0b0010_0000;
+    pub const ALM0EN: u8 = 0b0001_0000;
+    pub const CRSTRIM: u8 = 0b0000_0100;
+    pub const SQWFS: u8 = 0b0000_0011;
+    pub const ALMPOL: u8 = 0b1000_0000;
+    pub const ALMMSK: u8 = 0b0111_0000;
 }
 
-pub fn new_mcp7940n(transactions: &[I2cTrans]) -> Mcp794xx<interface::I2cInterface<I2cMock>> {
-    Mcp794xx::new_mcp7940n(I2cMock::new(&transactions))
-}
+macro_rules! create_destroy_ic {
+    ($ic:ident, $create:ident, $destroy:ident) => {
+        #[allow(unused)]
+        pub fn $create(
+            transactions: &[I2cTrans],
+        ) -> Mcp794xx<interface::I2cInterface<I2cMock>, ic::$ic> {
+            Mcp794xx::$create(I2cMock::new(&transactions))
+        }
 
-pub fn destroy_mcp7940n(dev: Mcp794xx<interface::I2cInterface<I2cMock>>) {
-    dev.destroy_mcp7940n().done();
+        #[allow(unused)]
+        pub fn $destroy(dev: Mcp794xx<interface::I2cInterface<I2cMock>, ic::$ic>) {
+            dev.$destroy().done();
+        }
+    };
 }
 
+create_destroy_ic!(Mcp7940m, new_mcp7940m, destroy_mcp7940m);
+create_destroy_ic!(Mcp7940n, new_mcp7940n, destroy_mcp7940n);
+create_destroy_ic!(Mcp79400, new_mcp79400, destroy_mcp79400);
+create_destroy_ic!(Mcp79401, new_mcp79401, destroy_mcp79401);
+create_destroy_ic!(Mcp79402, new_mcp79402, destroy_mcp79402);
+create_destroy_ic!(Mcp79410, new_mcp79410, destroy_mcp79410);
+create_destroy_ic!(Mcp79411, new_mcp79411, destroy_mcp79411);
+create_destroy_ic!(Mcp79412, new_mcp79412, destroy_mcp79412);
+
 #[macro_export]
 macro_rules! get_test {
     ($name:ident, $create_method:ident, $destroy_method:ident,
@@ -54,13 +104,24 @@ macro_rules! assert_invalid_input_data {
     };
 }
 
+#[macro_export]
+macro_rules! assert_feature_not_available {
+    ($result:expr) => {
+        match $result {
+            Err(Error::FeatureNotAvailable) => (),
+            _ => panic!("FeatureNotAvailable error not returned."),
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! set_invalid_test {
-    ($name:ident, $create_method:ident, $destroy_method:ident, $method:ident, $value:expr) => {
+    ($name:ident, $create_method:ident, $destroy_method:ident, $method:ident
+    $(, $value:expr)*) => {
         #[test]
         fn $name() {
             let mut dev = $create_method(&[]);
-            assert_invalid_input_data!(dev.$method($value));
+            assert_invalid_input_data!(dev.$method($($value),*));
             $destroy_method(dev);
         }
     };
@@ -85,11 +146,36 @@ macro_rules! for_all_ics {
     ($name:ident, $macroname:ident, $( $args:tt ),*) => {
         mod $name {
             use super::*;
-            $macroname!(for_mcp7940n, new_mcp7940n, destroy_mcp7940n, $($args),*);
+            $macroname!(for_mcp7940m, new_mcp7940m, destroy_mcp7940m, $($args),*);
+            for_battery_ics_body!($macroname, $($args),*);
         }
     };
 }
 
+// All ICs with a battery backup supply input (all but the MCP7940M)
+#[macro_export]
+macro_rules! for_battery_ics {
+    ($name:ident, $macroname:ident, $( $args:tt ),*) => {
+        mod $name {
+            use super::*;
+            for_battery_ics_body!($macroname, $($args),*);
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! for_battery_ics_body {
+    ($macroname:ident, $( $args:tt ),*) => {
+        $macroname!(for_mcp7940n, new_mcp7940n, destroy_mcp7940n, $($args),*);
+        $macroname!(for_mcp79400, new_mcp79400, destroy_mcp79400, $($args),*);
+        $macroname!(for_mcp79401, new_mcp79401, destroy_mcp79401, $($args),*);
+        $macroname!(for_mcp79402, new_mcp79402, destroy_mcp79402, $($args),*);
+        $macroname!(for_mcp79410, new_mcp79410, destroy_mcp79410, $($args),*);
+        $macroname!(for_mcp79411, new_mcp79411, destroy_mcp79411, $($args),*);
+        $macroname!(for_mcp79412, new_mcp79412, destroy_mcp79412, $($args),*);
+    };
+}
+
 #[macro_export]
 macro_rules! get_param_test {
     ($name:ident, $method:ident, $register1:ident, $value:expr, [ $( $read_bin:expr ),+ ]) => {
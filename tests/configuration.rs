@@ -1,7 +1,15 @@
 extern crate embedded_hal_mock as hal;
 use hal::i2c::Transaction as I2cTrans;
 mod common;
-use common::{destroy_mcp7940n, new_mcp7940n, BitFlags, Register, DEVICE_ADDRESS as DEV_ADDR};
+use common::{
+    destroy_mcp79400, destroy_mcp79401, destroy_mcp79402, destroy_mcp7940m, destroy_mcp7940n,
+    destroy_mcp79410, destroy_mcp79411, destroy_mcp79412, new_mcp79400, new_mcp79401, new_mcp79402,
+    new_mcp7940m, new_mcp7940n, new_mcp79410, new_mcp79411, new_mcp79412, BitFlags, Register,
+    DEVICE_ADDRESS as DEV_ADDR,
+};
+extern crate mcp794xx;
+use mcp794xx::interface::ReadData;
+use mcp794xx::{estimate_battery_life_days, ic, Capabilities, Error, SqWaveFreq};
 
 macro_rules! call_update_method {
     ($name:ident, $method:ident, $register:ident, $value:expr) => {
@@ -19,3 +27,488 @@ macro_rules! call_update_method {
 
 call_update_method!(can_enable, enable, SECONDS, BitFlags::ST);
 call_update_method!(can_disable, disable, SECONDS, 0);
+
+macro_rules! call_update_method_with_value {
+    ($name:ident, $method:ident, $register:ident, $initial:expr, $value:expr, $arg:expr) => {
+        for_all_ics!(
+            $name,
+            call_test,
+            $method,
+            [
+                I2cTrans::write_read(DEV_ADDR, vec![Register::$register], vec![$initial]),
+                I2cTrans::write(DEV_ADDR, vec![Register::$register, $value])
+            ],
+            $arg
+        );
+    };
+}
+
+for_battery_ics!(
+    can_en_vbat,
+    call_test,
+    set_vbat_en,
+    [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![0b101]),
+        I2cTrans::write(DEV_ADDR, vec![Register::WEEKDAY, BitFlags::VBATEN | 0b101])
+    ],
+    true
+);
+for_battery_ics!(
+    can_dis_vbat,
+    call_test,
+    set_vbat_en,
+    [
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::WEEKDAY],
+            vec![BitFlags::VBATEN | 0b101]
+        ),
+        I2cTrans::write(DEV_ADDR, vec![Register::WEEKDAY, 0b101])
+    ],
+    false
+);
+
+for_battery_ics!(
+    can_en_vbat_verified,
+    call_test,
+    set_vbat_en_verified,
+    [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::WEEKDAY, BitFlags::VBATEN]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![BitFlags::VBATEN])
+    ],
+    true
+);
+
+for_battery_ics!(
+    can_enable_battery_backup_verified,
+    call_test,
+    enable_battery_backup_verified,
+    [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![3]),
+        I2cTrans::write(DEV_ADDR, vec![Register::WEEKDAY, BitFlags::VBATEN | 3]),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::WEEKDAY],
+            vec![BitFlags::VBATEN | 3]
+        )
+    ]
+);
+
+#[test]
+fn enable_battery_backup_verified_detects_mismatch() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![3]),
+        I2cTrans::write(DEV_ADDR, vec![Register::WEEKDAY, BitFlags::VBATEN | 3]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![3]),
+    ];
+    let mut dev = new_mcp7940n(&trans);
+    match dev.enable_battery_backup_verified() {
+        Err(Error::VerificationFailed) => (),
+        _ => panic!("VerificationFailed error not returned."),
+    }
+    destroy_mcp7940n(dev);
+}
+
+#[test]
+fn en_vbat_verified_detects_mismatch() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::WEEKDAY, BitFlags::VBATEN]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![0]),
+    ];
+    let mut dev = new_mcp7940n(&trans);
+    match dev.set_vbat_en_verified(true) {
+        Err(Error::VerificationFailed) => (),
+        _ => panic!("VerificationFailed error not returned."),
+    }
+    destroy_mcp7940n(dev);
+}
+
+#[test]
+fn can_estimate_battery_life() {
+    // CR2032 coin cell (220 mAh) at 1 µA: 220000 h
+    let days = estimate_battery_life_days(220.0, 1.0);
+    assert!((days - 9166.667).abs() < 0.01);
+}
+
+macro_rules! has_eeprom_test {
+    ($name:ident, $create_method:ident, $destroy_method:ident, $expected:expr) => {
+        #[test]
+        fn $name() {
+            let dev = $create_method(&[]);
+            assert_eq!($expected, dev.has_eeprom());
+            $destroy_method(dev);
+        }
+    };
+}
+
+mod has_eeprom {
+    use super::*;
+    has_eeprom_test!(mcp7940n, new_mcp7940n, destroy_mcp7940n, false);
+    has_eeprom_test!(mcp79410, new_mcp79410, destroy_mcp79410, true);
+    has_eeprom_test!(mcp79411, new_mcp79411, destroy_mcp79411, true);
+    has_eeprom_test!(mcp79412, new_mcp79412, destroy_mcp79412, true);
+
+    #[test]
+    fn available_as_associated_const() {
+        use ic::Features;
+        let has_eeprom = [ic::Mcp7940n::HAS_EEPROM, ic::Mcp79412::HAS_EEPROM];
+        assert_eq!([false, true], has_eeprom);
+    }
+}
+
+macro_rules! capabilities_test {
+    ($name:ident, $create_method:ident, $destroy_method:ident,
+    $battery:expr, $eeprom:expr, $eui48:expr, $eui64:expr) => {
+        #[test]
+        fn $name() {
+            let dev = $create_method(&[]);
+            let expected = Capabilities {
+                battery_backup: $battery,
+                eeprom: $eeprom,
+                eui48: $eui48,
+                eui64: $eui64,
+                sram_size: 64,
+            };
+            assert_eq!(expected, dev.capabilities());
+            $destroy_method(dev);
+        }
+    };
+}
+
+mod capabilities {
+    use super::*;
+    capabilities_test!(
+        mcp7940m,
+        new_mcp7940m,
+        destroy_mcp7940m,
+        false,
+        false,
+        false,
+        false
+    );
+    capabilities_test!(
+        mcp7940n,
+        new_mcp7940n,
+        destroy_mcp7940n,
+        true,
+        false,
+        false,
+        false
+    );
+    capabilities_test!(
+        mcp79400,
+        new_mcp79400,
+        destroy_mcp79400,
+        true,
+        false,
+        false,
+        false
+    );
+    capabilities_test!(
+        mcp79401,
+        new_mcp79401,
+        destroy_mcp79401,
+        true,
+        false,
+        true,
+        false
+    );
+    capabilities_test!(
+        mcp79402,
+        new_mcp79402,
+        destroy_mcp79402,
+        true,
+        false,
+        false,
+        true
+    );
+    capabilities_test!(
+        mcp79410,
+        new_mcp79410,
+        destroy_mcp79410,
+        true,
+        true,
+        false,
+        false
+    );
+    capabilities_test!(
+        mcp79411,
+        new_mcp79411,
+        destroy_mcp79411,
+        true,
+        true,
+        true,
+        false
+    );
+    capabilities_test!(
+        mcp79412,
+        new_mcp79412,
+        destroy_mcp79412,
+        true,
+        true,
+        false,
+        true
+    );
+}
+
+macro_rules! iface_mut_test {
+    ($name:ident, $create_method:ident, $destroy_method:ident, $value:expr) => {
+        #[test]
+        fn $name() {
+            let trans = [I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::CONTROL],
+                vec![$value],
+            )];
+            let mut dev = $create_method(&trans);
+            let value = dev.iface_mut().read_register(Register::CONTROL).unwrap();
+            assert_eq!($value, value);
+            $destroy_method(dev);
+        }
+    };
+}
+
+for_all_ics!(can_access_interface, iface_mut_test, 0b1100_0011);
+
+macro_rules! square_wave_test {
+    ($name:ident, $frequency:ident, $initial:expr, $expected:expr) => {
+        call_update_method_with_value!(
+            $name,
+            enable_square_wave,
+            CONTROL,
+            $initial,
+            $expected,
+            SqWaveFreq::$frequency
+        );
+    };
+}
+
+square_wave_test!(
+    can_enable_square_wave_1hz,
+    _1Hz,
+    BitFlags::ALM0EN | BitFlags::SQWFS,
+    BitFlags::ALM0EN | BitFlags::SQWEN
+);
+square_wave_test!(
+    can_enable_square_wave_4_096hz,
+    _4_096Hz,
+    BitFlags::ALM1EN,
+    BitFlags::ALM1EN | BitFlags::SQWEN | 0b01
+);
+square_wave_test!(
+    can_enable_square_wave_8_192hz,
+    _8_192Hz,
+    BitFlags::OUT | 0b01,
+    BitFlags::OUT | BitFlags::SQWEN | 0b10
+);
+square_wave_test!(
+    can_enable_square_wave_32_768hz,
+    _32_768Hz,
+    BitFlags::SQWEN,
+    BitFlags::SQWEN | 0b11
+);
+
+for_all_ics!(
+    can_disable_square_wave,
+    call_test,
+    disable_square_wave,
+    [
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::CONTROL],
+            vec![BitFlags::SQWEN | BitFlags::ALM0EN | BitFlags::ALM1EN | 0b10]
+        ),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![
+                Register::CONTROL,
+                BitFlags::ALM0EN | BitFlags::ALM1EN | 0b10
+            ]
+        )
+    ]
+);
+
+call_update_method_with_value!(
+    can_set_output_pin_high,
+    set_output_pin,
+    CONTROL,
+    BitFlags::ALM0EN | 0b11,
+    BitFlags::OUT | BitFlags::ALM0EN | 0b11,
+    true
+);
+call_update_method_with_value!(
+    can_set_output_pin_low,
+    set_output_pin,
+    CONTROL,
+    BitFlags::OUT | BitFlags::SQWEN,
+    BitFlags::SQWEN,
+    false
+);
+
+mod oscillator_trim {
+    use super::*;
+
+    macro_rules! trim_test {
+        ($name:ident, $value:expr, $register:expr) => {
+            mod $name {
+                use super::*;
+                set_param_test!(can_set, set_oscillator_trim, OSCTRIM, $value, [$register]);
+                get_param_test!(can_get, get_oscillator_trim, OSCTRIM, $value, [$register]);
+            }
+        };
+    }
+
+    trim_test!(zero, 0, 0);
+    trim_test!(positive, 5, 0b1000_0101);
+    trim_test!(max_positive, 127, 0xFF);
+    trim_test!(negative, -5, 0b0000_0101);
+    trim_test!(max_negative, -127, 0x7F);
+
+    // A set sign bit with zero magnitude reads back as 0.
+    get_param_test!(
+        positive_zero,
+        get_oscillator_trim,
+        OSCTRIM,
+        0,
+        [0b1000_0000]
+    );
+
+    for_all_ics!(
+        cannot_set_minus_128,
+        set_invalid_test,
+        set_oscillator_trim,
+        (-128)
+    );
+}
+
+macro_rules! coarse_trim_test {
+    ($name:ident, $method:ident, $initial:expr, $value:expr) => {
+        for_all_ics!(
+            $name,
+            call_test,
+            $method,
+            [
+                I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], vec![$initial]),
+                I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, $value])
+            ]
+        );
+    };
+}
+
+coarse_trim_test!(
+    can_enable_coarse_trim,
+    enable_coarse_trim,
+    BitFlags::SQWEN | BitFlags::ALM1EN | 0b11,
+    BitFlags::SQWEN | BitFlags::ALM1EN | BitFlags::CRSTRIM | 0b11
+);
+coarse_trim_test!(
+    can_disable_coarse_trim,
+    disable_coarse_trim,
+    BitFlags::OUT | BitFlags::CRSTRIM | 0b01,
+    BitFlags::OUT | 0b01
+);
+
+mod ensure_clock_configured {
+    use super::*;
+
+    for_battery_ics!(
+        no_writes_if_configured_with_vbat,
+        call_test,
+        ensure_clock_configured,
+        [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![BitFlags::ST | 0x12]),
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::WEEKDAY],
+                vec![BitFlags::OSCRUN | BitFlags::VBATEN | 3]
+            )
+        ],
+        true
+    );
+
+    for_all_ics!(
+        no_writes_if_configured_without_vbat,
+        call_test,
+        ensure_clock_configured,
+        [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![BitFlags::ST]),
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::WEEKDAY],
+                vec![BitFlags::OSCRUN | 3]
+            )
+        ],
+        false
+    );
+
+    for_battery_ics!(
+        writes_both,
+        call_test,
+        ensure_clock_configured,
+        [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![0x12]),
+            I2cTrans::write(DEV_ADDR, vec![Register::SECONDS, BitFlags::ST | 0x12]),
+            I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![3]),
+            I2cTrans::write(DEV_ADDR, vec![Register::WEEKDAY, BitFlags::VBATEN | 3])
+        ],
+        true
+    );
+
+    for_all_ics!(
+        only_writes_vbat,
+        call_test,
+        ensure_clock_configured,
+        [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![BitFlags::ST]),
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::WEEKDAY],
+                vec![BitFlags::OSCRUN | BitFlags::VBATEN | 3]
+            ),
+            I2cTrans::write(DEV_ADDR, vec![Register::WEEKDAY, BitFlags::OSCRUN | 3])
+        ],
+        false
+    );
+
+    #[test]
+    fn mcp7940m_without_vbat() {
+        let trans = [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![0]),
+            I2cTrans::write(DEV_ADDR, vec![Register::SECONDS, BitFlags::ST]),
+            I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![1]),
+        ];
+        let mut dev = new_mcp7940m(&trans);
+        dev.ensure_clock_configured(false).unwrap();
+        destroy_mcp7940m(dev);
+    }
+
+    #[test]
+    fn mcp7940m_cannot_enable_vbat() {
+        let mut dev = new_mcp7940m(&[]);
+        assert_feature_not_available!(dev.ensure_clock_configured(true));
+        destroy_mcp7940m(dev);
+    }
+}
+
+#[test]
+fn mcp7940m_has_no_battery_backup() {
+    let mut dev = new_mcp7940m(&[]);
+    assert_feature_not_available!(dev.set_vbat_en(true));
+    assert_feature_not_available!(dev.set_vbat_en(false));
+    assert_feature_not_available!(dev.set_vbat_en_verified(true));
+    assert_feature_not_available!(dev.enable_battery_backup_verified());
+    destroy_mcp7940m(dev);
+}
+
+#[test]
+fn can_enable_mcp7940m() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::SECONDS, BitFlags::ST]),
+    ];
+    let mut dev = new_mcp7940m(&trans);
+    dev.enable().unwrap();
+    destroy_mcp7940m(dev);
+}
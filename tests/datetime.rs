@@ -1,9 +1,18 @@
 extern crate embedded_hal_mock as hal;
+use hal::delay::MockNoop;
 use hal::i2c::Transaction as I2cTrans;
 mod common;
-use common::{destroy_mcp7940n, new_mcp7940n, Register, DEVICE_ADDRESS as DEV_ADDR};
+use common::{
+    destroy_mcp79400, destroy_mcp79401, destroy_mcp79402, destroy_mcp7940m, destroy_mcp7940n,
+    destroy_mcp79410, destroy_mcp79411, destroy_mcp79412, new_mcp79400, new_mcp79401, new_mcp79402,
+    new_mcp7940m, new_mcp7940n, new_mcp79410, new_mcp79411, new_mcp79412, BitFlags, Register,
+    DEVICE_ADDRESS as DEV_ADDR,
+};
 extern crate mcp794xx;
-use mcp794xx::{Error, Hours, Rtcc};
+use mcp794xx::{
+    datetime_to_registers, registers_to_datetime, DateTime, Error, HourFormat, Hours, Rtcc,
+    Weekday, WeekdayConvention,
+};
 
 macro_rules! set_invalid_param_test {
     ($name:ident, $method:ident, $value:expr) => {
@@ -27,6 +36,27 @@ macro_rules! set_invalid_param_range_test {
 mod seconds {
     use super::*;
     get_param_test!(get, get_seconds, SECONDS, 12, [18]);
+    get_param_test!(
+        get_with_st_set,
+        get_seconds_with_run_state,
+        SECONDS,
+        (18, true),
+        [BitFlags::ST | 0x18]
+    );
+    get_param_test!(
+        get_with_st_clear,
+        get_seconds_with_run_state,
+        SECONDS,
+        (59, false),
+        [0x59]
+    );
+    get_param_test!(
+        get_raw,
+        read_seconds_byte,
+        SECONDS,
+        BitFlags::ST | 0x18,
+        [BitFlags::ST | 0x18]
+    );
     set_param_test!(set, set_seconds, SECONDS, 12, [18]);
     set_invalid_param_test!(invalid, set_seconds, 60);
 }
@@ -58,3 +88,1659 @@ mod hours_12h_pm {
     set_param_test!(set, set_hours, HOURS, Hours::PM(12), [0b0111_0010]);
     set_invalid_param_range_test!(invalid, set_hours, Hours::PM(0), Hours::PM(13));
 }
+
+mod hours_both {
+    use super::*;
+    get_param_test!(
+        get_pm,
+        get_hours_both,
+        HOURS,
+        (15, Hours::PM(3)),
+        [0b0110_0011]
+    );
+    get_param_test!(
+        get_am,
+        get_hours_both,
+        HOURS,
+        (0, Hours::AM(12)),
+        [0b0101_0010]
+    );
+    get_param_test!(
+        get_24h,
+        get_hours_both,
+        HOURS,
+        (21, Hours::H24(21)),
+        [0b0010_0001]
+    );
+}
+
+mod hour_format {
+    use super::*;
+
+    #[test]
+    fn can_round_trip_1pm() {
+        let trans = [
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::HOURS],
+                vec![BitFlags::H24_H12 | BitFlags::AM_PM | 0x01],
+            ),
+            I2cTrans::write(DEV_ADDR, vec![Register::HOURS, 0x13]),
+            I2cTrans::write_read(DEV_ADDR, vec![Register::HOURS], vec![0x13]),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![Register::HOURS, BitFlags::H24_H12 | BitFlags::AM_PM | 0x01],
+            ),
+        ];
+        let mut dev = new_mcp7940n(&trans);
+        dev.set_24h_mode().unwrap();
+        dev.set_12h_mode().unwrap();
+        destroy_mcp7940n(dev);
+    }
+
+    for_all_ics!(
+        can_set_12h_midnight,
+        call_test,
+        set_12h_mode,
+        [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::HOURS], vec![0x00]),
+            I2cTrans::write(DEV_ADDR, vec![Register::HOURS, BitFlags::H24_H12 | 0x12])
+        ]
+    );
+
+    for_all_ics!(
+        already_24h,
+        call_test,
+        set_24h_mode,
+        [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::HOURS],
+            vec![0x23]
+        )]
+    );
+
+    for_all_ics!(
+        already_12h,
+        call_test,
+        set_12h_mode,
+        [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::HOURS],
+            vec![BitFlags::H24_H12 | 0x11]
+        )]
+    );
+}
+
+mod weekday {
+    use super::*;
+    get_param_test!(get, get_weekday, WEEKDAY, 5, [BitFlags::VBATEN | 5]);
+    for_all_ics!(
+        set,
+        call_test,
+        set_weekday,
+        [
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::WEEKDAY],
+                vec![BitFlags::VBATEN | 1]
+            ),
+            I2cTrans::write(DEV_ADDR, vec![Register::WEEKDAY, BitFlags::VBATEN | 5])
+        ],
+        5
+    );
+    set_invalid_param_range_test!(invalid, set_weekday, 0, 8);
+}
+
+mod day {
+    use super::*;
+    get_param_test!(get, get_day, DAY, 23, [0b0010_0011]);
+    get_param_test!(get_raw, get_day_raw, DAY, 0b0010_0011, [0b0010_0011]);
+    set_param_test!(set, set_day, DAY, 23, [0b0010_0011]);
+
+    #[test]
+    fn raw_matches_decoded() {
+        let trans = [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::DAY], vec![0b0001_0111]),
+            I2cTrans::write_read(DEV_ADDR, vec![Register::DAY], vec![0b0001_0111]),
+        ];
+        let mut dev = new_mcp7940n(&trans);
+        let raw = dev.get_day_raw().unwrap();
+        let day = dev.get_day().unwrap();
+        assert_eq!(day, (raw >> 4) * 10 + (raw & 0xF));
+        destroy_mcp7940n(dev);
+    }
+    set_invalid_param_range_test!(invalid, set_day, 0, 32);
+}
+
+mod month {
+    use super::*;
+    get_param_test!(get, get_month, MONTH, 12, [0b0001_0010]);
+    get_param_test!(get_leap_year, get_month, MONTH, 2, [BitFlags::LPYR | 2]);
+    get_param_test!(
+        is_leap_year,
+        is_leap_year,
+        MONTH,
+        true,
+        [BitFlags::LPYR | 2]
+    );
+    get_param_test!(is_not_leap_year, is_leap_year, MONTH, false, [0b0001_0010]);
+    set_param_test!(set, set_month, MONTH, 12, [0b0001_0010]);
+    set_invalid_param_range_test!(invalid, set_month, 0, 13);
+}
+
+mod year {
+    use super::*;
+    get_param_test!(get, get_year, YEAR, 2099, [0b1001_1001]);
+    set_param_test!(set, set_year, YEAR, 2099, [0b1001_1001]);
+    set_invalid_param_range_test!(invalid, set_year, 1999, 2100);
+}
+
+macro_rules! read_datetime_test {
+    ($name:ident, $method:ident, [ $( $read_bin:expr ),+ ], $expected:expr) => {
+        for_all_ics!(
+            $name,
+            get_test,
+            $method,
+            [I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::SECONDS],
+                vec![$( $read_bin ),*]
+            )],
+            $expected
+        );
+    };
+}
+
+fn new_datetime(year: u16, month: u8, day: u8, weekday: u8, hour: Hours) -> DateTime {
+    DateTime {
+        year,
+        month,
+        day,
+        weekday,
+        hour,
+        minute: 59,
+        second: 58,
+    }
+}
+
+mod datetime {
+    use super::*;
+    read_datetime_test!(
+        get,
+        get_datetime,
+        [
+            BitFlags::ST | 0b0101_1000,
+            0b0101_1001,
+            0b0010_0011,
+            BitFlags::VBATEN | 4,
+            0b0011_0001,
+            0b0001_0010,
+            0b1001_1001
+        ],
+        new_datetime(2099, 12, 31, 4, Hours::H24(23))
+    );
+
+    for_all_ics!(
+        set,
+        call_test,
+        set_datetime,
+        [
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::SECONDS],
+                vec![BitFlags::ST, 0, 0, BitFlags::VBATEN | 1, 1, 1, 0]
+            ),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![
+                    Register::SECONDS,
+                    BitFlags::ST | 0b0101_1000,
+                    0b0101_1001,
+                    0b0111_0001,
+                    BitFlags::VBATEN | 4,
+                    0b0011_0001,
+                    0b0001_0010,
+                    0b1001_1001
+                ]
+            )
+        ],
+        (&new_datetime(2099, 12, 31, 4, Hours::PM(11)))
+    );
+
+    macro_rules! set_invalid_datetime_test {
+        ($name:ident, $datetime:expr) => {
+            for_all_ics!($name, set_invalid_test, set_datetime, (&$datetime));
+        };
+    }
+
+    set_invalid_datetime_test!(
+        too_small_year,
+        new_datetime(1999, 12, 31, 4, Hours::H24(23))
+    );
+    set_invalid_datetime_test!(too_big_year, new_datetime(2100, 12, 31, 4, Hours::H24(23)));
+    set_invalid_datetime_test!(too_big_month, new_datetime(2018, 13, 31, 4, Hours::H24(23)));
+    set_invalid_datetime_test!(too_small_day, new_datetime(2018, 12, 0, 4, Hours::H24(23)));
+    set_invalid_datetime_test!(
+        too_big_weekday,
+        new_datetime(2018, 12, 31, 8, Hours::H24(23))
+    );
+    set_invalid_datetime_test!(too_big_hours, new_datetime(2018, 12, 31, 4, Hours::H24(24)));
+
+    fn unchecked_write(seconds: u8) -> I2cTrans {
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![
+                Register::SECONDS,
+                seconds,
+                0b0101_1001,
+                0b0010_0011,
+                4,
+                0b0011_0001,
+                0b0001_0010,
+                0b1001_1001,
+            ],
+        )
+    }
+
+    for_all_ics!(
+        set_unchecked,
+        call_test,
+        set_datetime_unchecked,
+        [unchecked_write(0b0101_1000)],
+        (&new_datetime(2099, 12, 31, 4, Hours::H24(23)))
+    );
+
+    #[test]
+    fn set_unchecked_keeps_oscillator_enabled() {
+        let trans = [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![0]),
+            I2cTrans::write(DEV_ADDR, vec![Register::SECONDS, BitFlags::ST]),
+            unchecked_write(BitFlags::ST | 0b0101_1000),
+        ];
+        let mut dev = new_mcp79410(&trans);
+        dev.enable().unwrap();
+        dev.set_datetime_unchecked(&new_datetime(2099, 12, 31, 4, Hours::H24(23)))
+            .unwrap();
+        destroy_mcp79410(dev);
+    }
+
+    #[test]
+    fn cannot_set_unchecked_invalid() {
+        let mut dev = new_mcp7940n(&[]);
+        assert_invalid_input_data!(dev.set_datetime_unchecked(&new_datetime(
+            2100,
+            12,
+            31,
+            4,
+            Hours::H24(23)
+        )));
+        destroy_mcp7940n(dev);
+    }
+}
+
+mod registers {
+    use super::*;
+
+    #[test]
+    fn can_encode_datetime() {
+        let registers =
+            datetime_to_registers::<()>(&new_datetime(2099, 12, 31, 4, Hours::PM(11))).unwrap();
+        assert_eq!(
+            [
+                0b0101_1000,
+                0b0101_1001,
+                0b0111_0001,
+                4,
+                0b0011_0001,
+                0b0001_0010,
+                0b1001_1001
+            ],
+            registers
+        );
+    }
+
+    #[test]
+    fn cannot_encode_invalid_datetime() {
+        assert_invalid_input_data!(datetime_to_registers::<()>(&new_datetime(
+            2018,
+            2,
+            31,
+            0,
+            Hours::H24(12)
+        )));
+    }
+}
+
+mod registers_decoding {
+    use super::*;
+
+    #[test]
+    fn can_round_trip_datetime() {
+        let datetime = new_datetime(2019, 2, 28, 4, Hours::AM(12));
+        let registers = datetime_to_registers::<()>(&datetime).unwrap();
+        assert_eq!(datetime, registers_to_datetime::<()>(&registers).unwrap());
+    }
+
+    #[test]
+    fn ignores_status_bits() {
+        let registers = [
+            BitFlags::ST | 0b0101_1000,
+            0b0101_1001,
+            0b0010_0011,
+            BitFlags::VBATEN | 4,
+            0b0011_0001,
+            BitFlags::LPYR | 0b0001_0010,
+            0b1001_1001,
+        ];
+        assert_eq!(
+            new_datetime(2099, 12, 31, 4, Hours::H24(23)),
+            registers_to_datetime::<()>(&registers).unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_bcd() {
+        let registers = [
+            0b0101_1000,
+            0b0101_1010,
+            0b0010_0011,
+            4,
+            0b0011_0001,
+            0b0001_0010,
+            0,
+        ];
+        assert_invalid_input_data!(registers_to_datetime::<()>(&registers));
+    }
+
+    #[test]
+    fn rejects_out_of_range_field() {
+        let registers = [
+            0b0101_1000,
+            0b0101_1001,
+            0b0010_0011,
+            4,
+            0b0011_0001,
+            0b0001_0011,
+            0,
+        ];
+        assert_invalid_input_data!(registers_to_datetime::<()>(&registers));
+    }
+}
+
+mod rounded_to_minute {
+    use super::*;
+    read_datetime_test!(
+        round_down,
+        get_datetime_rounded_to_minute,
+        [
+            BitFlags::ST | 0b0010_1001,
+            0b0101_1001,
+            0b0001_0000,
+            4,
+            0b0001_0101,
+            0b0000_0110,
+            0b0001_1001
+        ],
+        DateTime {
+            second: 0,
+            ..new_datetime(2019, 6, 15, 4, Hours::H24(10))
+        }
+    );
+
+    read_datetime_test!(
+        round_up_with_hour_rollover,
+        get_datetime_rounded_to_minute,
+        [
+            BitFlags::ST | 0b0011_0000,
+            0b0101_1001,
+            0b0001_0000,
+            4,
+            0b0001_0101,
+            0b0000_0110,
+            0b0001_1001
+        ],
+        DateTime {
+            minute: 0,
+            second: 0,
+            ..new_datetime(2019, 6, 15, 4, Hours::H24(11))
+        }
+    );
+
+    read_datetime_test!(
+        round_up_with_year_rollover,
+        get_datetime_rounded_to_minute,
+        [
+            BitFlags::ST | 0b0101_0000,
+            0b0101_1001,
+            0b0111_0001,
+            7,
+            0b0011_0001,
+            0b0001_0010,
+            0b0001_1000
+        ],
+        DateTime {
+            minute: 0,
+            second: 0,
+            ..new_datetime(2019, 1, 1, 1, Hours::AM(12))
+        }
+    );
+}
+
+mod next_occurrence {
+    use super::*;
+
+    macro_rules! next_occurrence_test {
+        ($name:ident, [ $( $read_bin:expr ),+ ], $expected:expr, $hour:expr, $minute:expr) => {
+            for_all_ics!(
+                $name,
+                get_test,
+                next_occurrence_of,
+                [I2cTrans::write_read(
+                    DEV_ADDR,
+                    vec![Register::SECONDS],
+                    vec![$( $read_bin ),*]
+                )],
+                $expected,
+                $hour,
+                $minute
+            );
+        };
+    }
+
+    // 2019-06-15 05:59:58 -> 06:00 today
+    next_occurrence_test!(
+        before_target,
+        [BitFlags::ST | 0x58, 0x59, 0x05, 6, 0x15, 0x06, 0x19],
+        DateTime {
+            minute: 0,
+            second: 0,
+            ..new_datetime(2019, 6, 15, 6, Hours::H24(6))
+        },
+        (Hours::H24(6)),
+        0
+    );
+
+    // 2019-06-30 06:00:00 -> 06:00 AM tomorrow
+    next_occurrence_test!(
+        at_target,
+        [BitFlags::ST, 0x00, 0x06, 7, 0x30, 0x06, 0x19],
+        DateTime {
+            minute: 0,
+            second: 0,
+            ..new_datetime(2019, 7, 1, 1, Hours::AM(6))
+        },
+        (Hours::AM(6)),
+        0
+    );
+
+    // 2019-12-31 18:30:00 -> 06:00 tomorrow
+    next_occurrence_test!(
+        after_target,
+        [BitFlags::ST, 0x30, 0x18, 2, 0x31, 0x12, 0x19],
+        DateTime {
+            minute: 0,
+            second: 0,
+            ..new_datetime(2020, 1, 1, 3, Hours::H24(6))
+        },
+        (Hours::H24(6)),
+        0
+    );
+
+    for_all_ics!(
+        invalid_hour,
+        set_invalid_test,
+        next_occurrence_of,
+        (Hours::H24(24)),
+        0
+    );
+}
+
+mod gps {
+    use super::*;
+
+    macro_rules! set_from_gps_test {
+        ($name:ident, [ $( $write_bin:expr ),+ ], $( $value:expr ),+) => {
+            for_all_ics!(
+                $name,
+                call_test,
+                set_from_gps,
+                [
+                    I2cTrans::write_read(
+                        DEV_ADDR,
+                        vec![Register::SECONDS],
+                        vec![BitFlags::ST, 0, 0, BitFlags::VBATEN | 1, 1, 1, 0]
+                    ),
+                    I2cTrans::write(DEV_ADDR, vec![Register::SECONDS, $( $write_bin ),*])
+                ],
+                $( $value ),*
+            );
+        };
+    }
+
+    // Tuesday
+    set_from_gps_test!(
+        can_set,
+        [
+            BitFlags::ST | 0x58,
+            0x59,
+            0x23,
+            BitFlags::VBATEN | 2,
+            0x31,
+            0x12,
+            0x19
+        ],
+        2019,
+        12,
+        31,
+        23,
+        59,
+        58
+    );
+
+    // Second before the leap second of 2016-12-31 (Saturday)
+    set_from_gps_test!(
+        can_set_before_leap_second,
+        [
+            BitFlags::ST | 0x59,
+            0x59,
+            0x23,
+            BitFlags::VBATEN | 6,
+            0x31,
+            0x12,
+            0x16
+        ],
+        2016,
+        12,
+        31,
+        23,
+        59,
+        59
+    );
+
+    for_all_ics!(
+        leap_second,
+        set_invalid_test,
+        set_from_gps,
+        2016,
+        12,
+        31,
+        23,
+        59,
+        60
+    );
+    for_all_ics!(
+        no_leap_day,
+        set_invalid_test,
+        set_from_gps,
+        2019,
+        2,
+        29,
+        12,
+        0,
+        0
+    );
+    for_all_ics!(
+        too_big_hour,
+        set_invalid_test,
+        set_from_gps,
+        2019,
+        2,
+        28,
+        24,
+        0,
+        0
+    );
+}
+
+mod century_base {
+    use super::*;
+
+    macro_rules! century_test {
+        ($name:ident, $create_method:ident, $destroy_method:ident,
+        $transactions:expr, |$dev:ident| $body:block) => {
+            #[test]
+            fn $name() {
+                let trans = $transactions;
+                #[allow(unused_mut)]
+                let mut $dev = $create_method(&trans);
+                $body
+                $destroy_method($dev);
+            }
+        };
+    }
+
+    macro_rules! for_all_ics_century {
+        ($name:ident, $transactions:expr, |$dev:ident| $body:block) => {
+            mod $name {
+                use super::*;
+                century_test!(
+                    for_mcp7940n,
+                    new_mcp7940n,
+                    destroy_mcp7940n,
+                    $transactions,
+                    |$dev| $body
+                );
+                century_test!(
+                    for_mcp79410,
+                    new_mcp79410,
+                    destroy_mcp79410,
+                    $transactions,
+                    |$dev| $body
+                );
+                century_test!(
+                    for_mcp79411,
+                    new_mcp79411,
+                    destroy_mcp79411,
+                    $transactions,
+                    |$dev| $body
+                );
+                century_test!(
+                    for_mcp79412,
+                    new_mcp79412,
+                    destroy_mcp79412,
+                    $transactions,
+                    |$dev| $body
+                );
+            }
+        };
+    }
+
+    for_all_ics_century!(default_is_2000, [], |dev| {
+        assert_eq!(2000, dev.get_century_base());
+    });
+
+    for_all_ics_century!(can_get_after_set, [], |dev| {
+        dev.set_century_base(2100).unwrap();
+        assert_eq!(2100, dev.get_century_base());
+    });
+
+    for_all_ics_century!(cannot_set_invalid, [], |dev| {
+        assert_invalid_input_data!(dev.set_century_base(2050));
+        assert_eq!(2000, dev.get_century_base());
+    });
+
+    for_all_ics_century!(
+        can_get_year,
+        [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::YEAR],
+            vec![0x42]
+        )],
+        |dev| {
+            dev.set_century_base(2100).unwrap();
+            assert_eq!(2142, dev.get_year().unwrap());
+        }
+    );
+
+    for_all_ics_century!(
+        can_set_year,
+        [I2cTrans::write(DEV_ADDR, vec![Register::YEAR, 0x99])],
+        |dev| {
+            dev.set_century_base(2100).unwrap();
+            dev.set_year(2199).unwrap();
+        }
+    );
+
+    for_all_ics_century!(cannot_set_year_outside_century, [], |dev| {
+        dev.set_century_base(2100).unwrap();
+        assert_invalid_input_data!(dev.set_year(2099));
+        assert_invalid_input_data!(dev.set_year(2200));
+    });
+
+    for_all_ics_century!(
+        can_get_datetime,
+        [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::SECONDS],
+            vec![0x58, 0x59, 0x23, 2, 0x31, 0x12, 0x18]
+        )],
+        |dev| {
+            dev.set_century_base(2100).unwrap();
+            assert_eq!(2118, dev.get_datetime().unwrap().year);
+        }
+    );
+
+    for_all_ics_century!(
+        can_set_datetime,
+        [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![0; 7]),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![Register::SECONDS, 0x58, 0x59, 0x23, 2, 0x31, 0x12, 0x18]
+            )
+        ],
+        |dev| {
+            dev.set_century_base(2100).unwrap();
+            let datetime = DateTime {
+                year: 2118,
+                month: 12,
+                day: 31,
+                weekday: 2,
+                hour: Hours::H24(23),
+                minute: 59,
+                second: 58,
+            };
+            dev.set_datetime(&datetime).unwrap();
+        }
+    );
+}
+
+mod datetime_checked {
+    use super::*;
+
+    read_datetime_test!(
+        get,
+        get_datetime_checked,
+        [
+            BitFlags::ST | 0b0101_1000,
+            0b0101_1001,
+            0b0010_0011,
+            BitFlags::VBATEN | 4,
+            0b0011_0001,
+            BitFlags::LPYR | 0b0001_0010,
+            0b1001_1001
+        ],
+        new_datetime(2099, 12, 31, 4, Hours::H24(23))
+    );
+
+    macro_rules! get_invalid_test {
+        ($name:ident, $create_method:ident, $destroy_method:ident, [ $( $read_bin:expr ),+ ]) => {
+            #[test]
+            fn $name() {
+                let trans = [I2cTrans::write_read(
+                    DEV_ADDR,
+                    vec![Register::SECONDS],
+                    vec![$( $read_bin ),*],
+                )];
+                let mut dev = $create_method(&trans);
+                assert_invalid_input_data!(dev.get_datetime_checked());
+                $destroy_method(dev);
+            }
+        };
+    }
+
+    for_all_ics!(
+        invalid_bcd_minutes,
+        get_invalid_test,
+        [0x58, 0x5A, 0x23, 4, 0x31, 0x12, 0x99]
+    );
+    for_all_ics!(
+        invalid_bcd_year,
+        get_invalid_test,
+        [0x58, 0x59, 0x23, 4, 0x31, 0x12, 0xF0]
+    );
+    for_all_ics!(uninitialized_day, get_invalid_test, [0, 0, 0, 0, 0, 0, 0]);
+}
+
+mod future_weekday {
+    use super::*;
+
+    macro_rules! future_weekday_test {
+        ($name:ident, [ $( $read_bin:expr ),+ ], $expected:expr, $days:expr, $convention:ident) => {
+            for_all_ics!(
+                $name,
+                get_test,
+                weekday_of_future_date,
+                [I2cTrans::write_read(
+                    DEV_ADDR,
+                    vec![Register::SECONDS],
+                    vec![$( $read_bin ),*]
+                )],
+                $expected,
+                $days,
+                (WeekdayConvention::$convention)
+            );
+        };
+    }
+
+    // Monday 2019-03-04
+    future_weekday_test!(
+        today_monday_first,
+        [0, 0, 0x12, 1, 0x04, 0x03, 0x19],
+        1,
+        0,
+        MondayFirst
+    );
+    future_weekday_test!(
+        today_sunday_first,
+        [0, 0, 0x12, 1, 0x04, 0x03, 0x19],
+        2,
+        0,
+        SundayFirst
+    );
+    // Sunday 2019-04-07
+    future_weekday_test!(
+        across_month,
+        [0, 0, 0x12, 1, 0x04, 0x03, 0x19],
+        7,
+        34,
+        MondayFirst
+    );
+    // Wednesday 2020-01-01
+    future_weekday_test!(
+        across_year,
+        [0, 0, 0x12, 2, 0x31, 0x12, 0x19],
+        4,
+        1,
+        SundayFirst
+    );
+    // Thursday 2020-03-05 across leap day
+    future_weekday_test!(
+        across_leap_day,
+        [0, 0, 0x12, 5, 0x28, 0x02, 0x20],
+        4,
+        6,
+        MondayFirst
+    );
+}
+
+mod drift {
+    use super::*;
+
+    macro_rules! drift_test {
+        ($name:ident, $expected:expr, $datetime:expr) => {
+            for_all_ics!(
+                $name,
+                get_test,
+                drift_vs,
+                [I2cTrans::write_read(
+                    DEV_ADDR,
+                    vec![Register::SECONDS],
+                    vec![BitFlags::ST | 0x58, 0x59, 0x23, 2, 0x31, 0x12, 0x19]
+                )],
+                $expected,
+                (&$datetime)
+            );
+        };
+    }
+
+    drift_test!(none, 0, new_datetime(2019, 12, 31, 2, Hours::H24(23)));
+    drift_test!(
+        ahead,
+        3,
+        DateTime {
+            second: 55,
+            ..new_datetime(2019, 12, 31, 2, Hours::H24(23))
+        }
+    );
+    drift_test!(
+        behind_across_year,
+        -4,
+        DateTime {
+            minute: 0,
+            second: 2,
+            ..new_datetime(2020, 1, 1, 3, Hours::AM(12))
+        }
+    );
+}
+
+mod change_convention {
+    use super::*;
+
+    macro_rules! change_convention_test {
+        ($name:ident, $from:ident, $to:ident, $read:expr, $write:expr) => {
+            for_all_ics!(
+                $name,
+                call_test,
+                change_convention,
+                [
+                    I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![$read]),
+                    I2cTrans::write(DEV_ADDR, vec![Register::WEEKDAY, $write])
+                ],
+                (WeekdayConvention::$from),
+                (WeekdayConvention::$to)
+            );
+        };
+    }
+
+    change_convention_test!(
+        sunday_to_monday_first_on_sunday,
+        SundayFirst,
+        MondayFirst,
+        BitFlags::VBATEN | 1,
+        BitFlags::VBATEN | 7
+    );
+    change_convention_test!(
+        sunday_to_monday_first_on_saturday,
+        SundayFirst,
+        MondayFirst,
+        BitFlags::VBATEN | 7,
+        BitFlags::VBATEN | 6
+    );
+    change_convention_test!(
+        monday_to_sunday_first_on_sunday,
+        MondayFirst,
+        SundayFirst,
+        7,
+        1
+    );
+
+    for_all_ics!(
+        same_convention_is_no_op,
+        call_test,
+        change_convention,
+        [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::WEEKDAY],
+            vec![BitFlags::VBATEN | 3]
+        )],
+        (WeekdayConvention::MondayFirst),
+        (WeekdayConvention::MondayFirst)
+    );
+
+    #[test]
+    fn cannot_change_invalid_weekday() {
+        let trans = [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::WEEKDAY],
+            vec![BitFlags::VBATEN],
+        )];
+        let mut dev = new_mcp7940n(&trans);
+        assert_invalid_input_data!(dev.change_convention(
+            WeekdayConvention::SundayFirst,
+            WeekdayConvention::MondayFirst
+        ));
+        destroy_mcp7940n(dev);
+    }
+}
+
+mod pps {
+    use super::*;
+    use std::cell::Cell;
+
+    macro_rules! set_on_pps_test {
+        ($name:ident, $create_method:ident, $destroy_method:ident, $datetime:expr) => {
+            #[test]
+            fn $name() {
+                let trans = [
+                    I2cTrans::write_read(
+                        DEV_ADDR,
+                        vec![Register::SECONDS],
+                        vec![BitFlags::ST | 0x12, 0, 0, BitFlags::VBATEN | 1, 1, 1, 0],
+                    ),
+                    I2cTrans::write(
+                        DEV_ADDR,
+                        vec![
+                            Register::SECONDS,
+                            0x58,
+                            0x59,
+                            0x23,
+                            BitFlags::VBATEN | 2,
+                            0x31,
+                            0x12,
+                            0x19,
+                        ],
+                    ),
+                    I2cTrans::write(DEV_ADDR, vec![Register::SECONDS, BitFlags::ST | 0x58]),
+                ];
+                let mut dev = $create_method(&trans);
+                let calls = Cell::new(0);
+                dev.set_datetime_on_pps(&$datetime, || calls.set(calls.get() + 1))
+                    .unwrap();
+                assert_eq!(1, calls.get());
+                $destroy_method(dev);
+            }
+        };
+    }
+
+    for_all_ics!(
+        can_set,
+        set_on_pps_test,
+        (new_datetime(2019, 12, 31, 2, Hours::H24(23)))
+    );
+
+    #[test]
+    fn does_not_wait_for_invalid_datetime() {
+        let mut dev = new_mcp7940n(&[]);
+        let datetime = DateTime {
+            minute: 60,
+            ..new_datetime(2019, 12, 31, 2, Hours::H24(23))
+        };
+        let mut called = false;
+        assert_invalid_input_data!(dev.set_datetime_on_pps(&datetime, || called = true));
+        assert!(!called);
+        destroy_mcp7940n(dev);
+    }
+}
+
+mod set_if_drifted {
+    use super::*;
+
+    const CURRENT: [u8; 7] = [BitFlags::ST | 0x50, 0x59, 0x23, 2, 0x31, 0x12, 0x19];
+
+    for_all_ics!(
+        within_threshold,
+        get_test,
+        set_datetime_if_drifted,
+        [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::SECONDS],
+            CURRENT.to_vec()
+        )],
+        false,
+        (&new_datetime(2019, 12, 31, 2, Hours::H24(23))),
+        8
+    );
+
+    for_all_ics!(
+        beyond_threshold,
+        get_test,
+        set_datetime_if_drifted,
+        [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], CURRENT.to_vec()),
+            I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], CURRENT.to_vec()),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![
+                    Register::SECONDS,
+                    BitFlags::ST | 0x58,
+                    0x59,
+                    0x23,
+                    2,
+                    0x31,
+                    0x12,
+                    0x19
+                ]
+            )
+        ],
+        true,
+        (&new_datetime(2019, 12, 31, 2, Hours::H24(23))),
+        7
+    );
+
+    for_all_ics!(
+        device_ahead_beyond_threshold,
+        get_test,
+        set_datetime_if_drifted,
+        [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], CURRENT.to_vec()),
+            I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], CURRENT.to_vec()),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![
+                    Register::SECONDS,
+                    BitFlags::ST | 0x40,
+                    0x59,
+                    0x23,
+                    2,
+                    0x31,
+                    0x12,
+                    0x19
+                ]
+            )
+        ],
+        true,
+        (&DateTime {
+            second: 40,
+            ..new_datetime(2019, 12, 31, 2, Hours::H24(23))
+        }),
+        5
+    );
+
+    for_all_ics!(
+        cannot_set_invalid,
+        set_invalid_test,
+        set_datetime_if_drifted,
+        (&DateTime {
+            second: 60,
+            ..new_datetime(2019, 12, 31, 2, Hours::H24(23))
+        }),
+        1000
+    );
+}
+
+mod representable_range {
+    use super::*;
+
+    fn new_h24_datetime(year: u16, month: u8, day: u8, weekday: u8, hms: (u8, u8, u8)) -> DateTime {
+        DateTime {
+            year,
+            month,
+            day,
+            weekday,
+            hour: Hours::H24(hms.0),
+            minute: hms.1,
+            second: hms.2,
+        }
+    }
+
+    macro_rules! range_test {
+        ($name:ident, $create_method:ident, $destroy_method:ident, $method:ident, $expected:expr) => {
+            #[test]
+            fn $name() {
+                let dev = $create_method(&[]);
+                assert_eq!($expected, dev.$method());
+                $destroy_method(dev);
+            }
+        };
+    }
+
+    for_all_ics!(
+        default_min,
+        range_test,
+        min_datetime,
+        (new_h24_datetime(2000, 1, 1, 6, (0, 0, 0)))
+    );
+    for_all_ics!(
+        default_max,
+        range_test,
+        max_datetime,
+        (new_h24_datetime(2099, 12, 31, 4, (23, 59, 59)))
+    );
+
+    #[test]
+    fn custom_century_base() {
+        let mut dev = new_mcp7940n(&[]);
+        dev.set_century_base(2100).unwrap();
+        assert_eq!(
+            new_h24_datetime(2100, 1, 1, 5, (0, 0, 0)),
+            dev.min_datetime()
+        );
+        assert_eq!(
+            new_h24_datetime(2199, 12, 31, 2, (23, 59, 59)),
+            dev.max_datetime()
+        );
+        destroy_mcp7940n(dev);
+    }
+}
+
+mod sleep_until_second {
+    use super::*;
+
+    fn read_seconds(seconds: u8) -> I2cTrans {
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::SECONDS],
+            vec![BitFlags::ST | seconds],
+        )
+    }
+
+    for_all_ics!(
+        returns_when_already_there,
+        call_test,
+        sleep_until_second,
+        [read_seconds(0x30)],
+        30,
+        (&mut MockNoop::new())
+    );
+
+    for_all_ics!(
+        polls_until_target,
+        call_test,
+        sleep_until_second,
+        [
+            read_seconds(0x57),
+            read_seconds(0x58),
+            read_seconds(0x58),
+            read_seconds(0x59),
+            read_seconds(0x00)
+        ],
+        0,
+        (&mut MockNoop::new())
+    );
+
+    #[test]
+    fn times_out_if_seconds_are_stuck() {
+        let trans = vec![read_seconds(0x12); 6100];
+        let mut dev = new_mcp7940n(&trans);
+        match dev.sleep_until_second(13, &mut MockNoop::new()) {
+            Err(Error::Timeout) => (),
+            _ => panic!("Timeout error not returned."),
+        }
+        destroy_mcp7940n(dev);
+    }
+
+    for_all_ics!(
+        cannot_set_invalid_target,
+        set_invalid_test,
+        sleep_until_second,
+        60,
+        (&mut MockNoop::new())
+    );
+}
+
+mod wall_clock {
+    use super::*;
+
+    fn new_datetime() -> DateTime {
+        DateTime {
+            year: 2018,
+            month: 8,
+            day: 13,
+            weekday: 1,
+            hour: Hours::H24(23),
+            minute: 59,
+            second: 58,
+        }
+    }
+
+    fn setup_transactions() -> Vec<I2cTrans> {
+        vec![
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::SECONDS],
+                vec![0, 0, 0, BitFlags::PWRFAIL | 3, 1, 1, 0],
+            ),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![
+                    Register::SECONDS,
+                    BitFlags::ST | 0x58,
+                    0x59,
+                    0b0111_0001,
+                    BitFlags::VBATEN | 1,
+                    0x13,
+                    0x08,
+                    0x18,
+                ],
+            ),
+        ]
+    }
+
+    macro_rules! setup_test {
+        ($name:ident, $create_method:ident, $destroy_method:ident, $polls:expr) => {
+            #[test]
+            fn $name() {
+                let mut trans = setup_transactions();
+                let polls: &[u8] = &$polls;
+                for weekday in polls {
+                    trans.push(I2cTrans::write_read(
+                        DEV_ADDR,
+                        vec![Register::WEEKDAY],
+                        vec![*weekday],
+                    ));
+                }
+                let mut dev = $create_method(&trans);
+                dev.setup_wall_clock(&new_datetime(), HourFormat::H12, &mut MockNoop::new())
+                    .unwrap();
+                $destroy_method(dev);
+            }
+        };
+    }
+
+    for_battery_ics!(
+        can_setup,
+        setup_test,
+        [
+            BitFlags::VBATEN | 1,
+            BitFlags::OSCRUN | BitFlags::VBATEN | 1
+        ]
+    );
+
+    macro_rules! setup_fails_test {
+        ($name:ident, $create_method:ident, $destroy_method:ident, $weekday:expr) => {
+            #[test]
+            fn $name() {
+                let mut trans = setup_transactions();
+                for _ in 0..100 {
+                    trans.push(I2cTrans::write_read(
+                        DEV_ADDR,
+                        vec![Register::WEEKDAY],
+                        vec![$weekday],
+                    ));
+                }
+                let mut dev = $create_method(&trans);
+                match dev.setup_wall_clock(&new_datetime(), HourFormat::H12, &mut MockNoop::new()) {
+                    Err(Error::OscillatorFailure) => (),
+                    _ => panic!("OscillatorFailure error not returned."),
+                }
+                $destroy_method(dev);
+            }
+        };
+    }
+
+    for_battery_ics!(oscillator_does_not_start, setup_fails_test, 1);
+
+    #[test]
+    fn mcp7940m_does_not_enable_vbat() {
+        let mut trans = setup_transactions();
+        trans[1] = I2cTrans::write(
+            DEV_ADDR,
+            vec![
+                Register::SECONDS,
+                BitFlags::ST | 0x58,
+                0x59,
+                0b0111_0001,
+                1,
+                0x13,
+                0x08,
+                0x18,
+            ],
+        );
+        trans.push(I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::WEEKDAY],
+            vec![BitFlags::OSCRUN | 1],
+        ));
+        let mut dev = new_mcp7940m(&trans);
+        dev.setup_wall_clock(&new_datetime(), HourFormat::H12, &mut MockNoop::new())
+            .unwrap();
+        destroy_mcp7940m(dev);
+    }
+
+    fn invalid_datetime() -> DateTime {
+        DateTime {
+            hour: Hours::AM(13),
+            ..new_datetime()
+        }
+    }
+
+    for_all_ics!(
+        cannot_setup_with_invalid_hour,
+        set_invalid_test,
+        setup_wall_clock,
+        (&invalid_datetime()),
+        (HourFormat::H24),
+        (&mut MockNoop::new())
+    );
+}
+
+mod weekday_convention {
+    use super::*;
+    use hal::i2c::Mock as I2cMock;
+    use mcp794xx::Mcp794xx;
+
+    macro_rules! convention_tests {
+        ($name:ident, $create:ident, $create_with_convention:ident, $destroy:ident) => {
+            mod $name {
+                use super::*;
+
+                #[test]
+                fn default_is_iso() {
+                    let dev = Mcp794xx::$create(I2cMock::new(&[]));
+                    assert_eq!(WeekdayConvention::MondayFirst, dev.get_weekday_convention());
+                    dev.$destroy().done();
+                }
+
+                #[test]
+                fn stores_convention() {
+                    let dev = Mcp794xx::$create_with_convention(
+                        I2cMock::new(&[]),
+                        WeekdayConvention::SundayFirst,
+                    );
+                    assert_eq!(WeekdayConvention::SundayFirst, dev.get_weekday_convention());
+                    dev.$destroy().done();
+                }
+
+                #[test]
+                fn can_get_weekday_iso() {
+                    let trans = [
+                        I2cTrans::write_read(
+                            DEV_ADDR,
+                            vec![Register::WEEKDAY],
+                            vec![BitFlags::OSCRUN | 1],
+                        ),
+                        I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![2]),
+                    ];
+                    let mut dev = Mcp794xx::$create_with_convention(
+                        I2cMock::new(&trans),
+                        WeekdayConvention::SundayFirst,
+                    );
+                    assert_eq!(7, dev.get_weekday_iso().unwrap());
+                    assert_eq!(1, dev.get_weekday_iso().unwrap());
+                    dev.$destroy().done();
+                }
+
+                #[test]
+                fn cannot_get_invalid_weekday_iso() {
+                    let trans = [I2cTrans::write_read(
+                        DEV_ADDR,
+                        vec![Register::WEEKDAY],
+                        vec![BitFlags::VBATEN],
+                    )];
+                    let mut dev = Mcp794xx::$create(I2cMock::new(&trans));
+                    assert_invalid_input_data!(dev.get_weekday_iso());
+                    dev.$destroy().done();
+                }
+
+                // 2019-03-10 is a Sunday
+                #[test]
+                fn set_from_gps_uses_convention() {
+                    let trans = [
+                        I2cTrans::write_read(
+                            DEV_ADDR,
+                            vec![Register::SECONDS],
+                            vec![BitFlags::ST, 0, 0, 1, 1, 1, 0],
+                        ),
+                        I2cTrans::write(
+                            DEV_ADDR,
+                            vec![
+                                Register::SECONDS,
+                                BitFlags::ST,
+                                0,
+                                0x12,
+                                1,
+                                0x10,
+                                0x03,
+                                0x19,
+                            ],
+                        ),
+                    ];
+                    let mut dev = Mcp794xx::$create_with_convention(
+                        I2cMock::new(&trans),
+                        WeekdayConvention::SundayFirst,
+                    );
+                    dev.set_from_gps(2019, 3, 10, 12, 0, 0).unwrap();
+                    dev.$destroy().done();
+                }
+            }
+        };
+    }
+
+    convention_tests!(
+        mcp7940n,
+        new_mcp7940n,
+        new_mcp7940n_with_convention,
+        destroy_mcp7940n
+    );
+    convention_tests!(
+        mcp79410,
+        new_mcp79410,
+        new_mcp79410_with_convention,
+        destroy_mcp79410
+    );
+    convention_tests!(
+        mcp79411,
+        new_mcp79411,
+        new_mcp79411_with_convention,
+        destroy_mcp79411
+    );
+    convention_tests!(
+        mcp79412,
+        new_mcp79412,
+        new_mcp79412_with_convention,
+        destroy_mcp79412
+    );
+}
+
+mod format_time {
+    use super::*;
+
+    macro_rules! format_test {
+        ($name:ident, $create_method:ident, $destroy_method:ident,
+         [ $( $registers:expr ),+ ], $expected:expr) => {
+            #[test]
+            fn $name() {
+                let trans = [I2cTrans::write_read(
+                    DEV_ADDR,
+                    vec![Register::SECONDS],
+                    vec![$( $registers ),*],
+                )];
+                let mut dev = $create_method(&trans);
+                let mut buf = [0; 8];
+                assert_eq!(8, dev.format_time_into(&mut buf).unwrap());
+                assert_eq!($expected, &buf);
+                $destroy_method(dev);
+            }
+        };
+    }
+
+    for_all_ics!(
+        h24,
+        format_test,
+        [BitFlags::ST | 0x09, 0x05, 0x07],
+        b"07:05:09"
+    );
+    for_all_ics!(h12_pm, format_test, [0x58, 0x59, 0b0111_0001], b"23:59:58");
+    for_all_ics!(
+        h12_midnight,
+        format_test,
+        [0x00, 0x30, 0b0101_0010],
+        b"00:30:00"
+    );
+}
+
+mod format_date {
+    use super::*;
+
+    macro_rules! format_test {
+        ($name:ident, $create_method:ident, $destroy_method:ident,
+         $century_base:expr, [ $( $registers:expr ),+ ], $expected:expr) => {
+            #[test]
+            fn $name() {
+                let trans = [I2cTrans::write_read(
+                    DEV_ADDR,
+                    vec![Register::DAY],
+                    vec![$( $registers ),*],
+                )];
+                let mut dev = $create_method(&trans);
+                dev.set_century_base($century_base).unwrap();
+                let mut buf = [0; 10];
+                assert_eq!(10, dev.format_date_into(&mut buf).unwrap());
+                assert_eq!($expected, &buf);
+                $destroy_method(dev);
+            }
+        };
+    }
+
+    for_all_ics!(
+        default_century,
+        format_test,
+        2000,
+        [0x31, 0x12, 0x19],
+        b"2019-12-31"
+    );
+    for_all_ics!(
+        leap_year,
+        format_test,
+        2000,
+        [0x29, BitFlags::LPYR | 0x02, 0x20],
+        b"2020-02-29"
+    );
+    for_all_ics!(
+        other_century,
+        format_test,
+        2100,
+        [0x01, 0x01, 0x05],
+        b"2105-01-01"
+    );
+}
+
+mod weekday_enum {
+    use super::*;
+    use hal::i2c::Mock as I2cMock;
+    use mcp794xx::Mcp794xx;
+
+    for_all_ics!(
+        can_get,
+        get_test,
+        get_weekday_enum,
+        [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::WEEKDAY],
+            vec![BitFlags::OSCRUN | 3]
+        )],
+        (Weekday::Wednesday)
+    );
+
+    for_all_ics!(
+        can_get_sunday,
+        get_test,
+        get_weekday_enum,
+        [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::WEEKDAY],
+            vec![7]
+        )],
+        (Weekday::Sunday)
+    );
+
+    for_all_ics!(
+        can_set,
+        call_test,
+        set_weekday_enum,
+        [
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::WEEKDAY],
+                vec![BitFlags::VBATEN | 3]
+            ),
+            I2cTrans::write(DEV_ADDR, vec![Register::WEEKDAY, BitFlags::VBATEN | 1])
+        ],
+        (Weekday::Monday)
+    );
+
+    #[test]
+    fn cannot_get_invalid() {
+        let trans = [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::WEEKDAY],
+            vec![0],
+        )];
+        let mut dev = new_mcp7940n(&trans);
+        assert_invalid_input_data!(dev.get_weekday_enum());
+        destroy_mcp7940n(dev);
+    }
+
+    #[test]
+    fn uses_convention() {
+        let trans = [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![1]),
+            I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![1]),
+            I2cTrans::write(DEV_ADDR, vec![Register::WEEKDAY, 2]),
+        ];
+        let mut dev = Mcp794xx::new_mcp79410_with_convention(
+            I2cMock::new(&trans),
+            WeekdayConvention::SundayFirst,
+        );
+        assert_eq!(Weekday::Sunday, dev.get_weekday_enum().unwrap());
+        dev.set_weekday_enum(Weekday::Monday).unwrap();
+        dev.destroy_mcp79410().done();
+    }
+}
+
+mod check_monotonic {
+    use super::*;
+
+    macro_rules! monotonic_test {
+        ($name:ident, $second:expr, $expected:expr) => {
+            for_all_ics!(
+                $name,
+                get_test,
+                check_monotonic,
+                [
+                    I2cTrans::write_read(
+                        DEV_ADDR,
+                        vec![Register::SECONDS],
+                        vec![BitFlags::ST | 0x59, 0x59, 0x23, 2, 0x31, 0x12, 0x18]
+                    ),
+                    I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], $second.to_vec())
+                ],
+                $expected,
+                (&mut MockNoop::new())
+            );
+        };
+    }
+
+    monotonic_test!(advancing, [BitFlags::ST, 0, 0, 3, 1, 1, 0x19], true);
+    monotonic_test!(
+        stuck,
+        [BitFlags::ST | 0x59, 0x59, 0x23, 2, 0x31, 0x12, 0x18],
+        false
+    );
+    monotonic_test!(
+        backward,
+        [BitFlags::ST | 0x58, 0x59, 0x23, 2, 0x31, 0x12, 0x18],
+        false
+    );
+}
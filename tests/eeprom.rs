@@ -0,0 +1,452 @@
+extern crate embedded_hal_mock as hal;
+use hal::i2c::Transaction as I2cTrans;
+mod common;
+use common::{
+    destroy_mcp79400, destroy_mcp79401, destroy_mcp79402, destroy_mcp7940m, destroy_mcp7940n,
+    destroy_mcp79410, destroy_mcp79411, destroy_mcp79412, new_mcp79400, new_mcp79401, new_mcp79402,
+    new_mcp7940m, new_mcp7940n, new_mcp79410, new_mcp79411, new_mcp79412, Register,
+    DEVICE_ADDRESS as DEV_ADDR, EEPROM_ADDRESS as EEPROM_ADDR,
+};
+extern crate embedded_hal;
+extern crate mcp794xx;
+use mcp794xx::{DetectedVariant, Error, Mcp794xx};
+
+macro_rules! for_eeprom_ics {
+    ($name:ident, $macroname:ident, $( $args:tt ),*) => {
+        mod $name {
+            use super::*;
+            $macroname!(for_mcp79410, new_mcp79410, destroy_mcp79410, $($args),*);
+            $macroname!(for_mcp79411, new_mcp79411, destroy_mcp79411, $($args),*);
+            $macroname!(for_mcp79412, new_mcp79412, destroy_mcp79412, $($args),*);
+        }
+    };
+}
+
+macro_rules! read_eeprom_test {
+    ($name:ident, $create_method:ident, $destroy_method:ident, $address:expr) => {
+        #[test]
+        fn $name() {
+            let trans = [I2cTrans::write_read(
+                EEPROM_ADDR,
+                vec![$address],
+                vec![0xAB, 0xCD, 0xEF],
+            )];
+            let mut dev = $create_method(&trans);
+            let mut buf = [0; 3];
+            dev.read_eeprom($address, &mut buf).unwrap();
+            assert_eq!([0xAB, 0xCD, 0xEF], buf);
+            $destroy_method(dev);
+        }
+    };
+}
+
+for_eeprom_ics!(can_read_start, read_eeprom_test, 0);
+for_eeprom_ics!(can_read_end, read_eeprom_test, 125);
+
+for_eeprom_ics!(
+    can_write,
+    call_test,
+    write_eeprom,
+    [I2cTrans::write(EEPROM_ADDR, vec![0x10, 1, 2, 3])],
+    0x10,
+    (&[1, 2, 3])
+);
+
+for_eeprom_ics!(
+    can_write_whole_page,
+    call_test,
+    write_eeprom,
+    [I2cTrans::write(
+        EEPROM_ADDR,
+        [&[0x78][..], &[0x5A; 8]].concat()
+    )],
+    0x78,
+    (&[0x5A; 8])
+);
+
+for_eeprom_ics!(
+    cannot_write_across_page,
+    set_invalid_test,
+    write_eeprom,
+    6,
+    (&[1, 2, 3])
+);
+
+for_eeprom_ics!(
+    cannot_write_past_end,
+    set_invalid_test,
+    write_eeprom,
+    127,
+    (&[1, 2])
+);
+
+#[test]
+fn cannot_read_past_end() {
+    let mut dev = new_mcp79410(&[]);
+    let mut buf = [0; 3];
+    assert_invalid_input_data!(dev.read_eeprom(126, &mut buf));
+    assert_invalid_input_data!(dev.read_eeprom(128, &mut buf[..1]));
+    destroy_mcp79410(dev);
+}
+
+macro_rules! no_eeprom_test {
+    ($name:ident, $create_method:ident, $destroy_method:ident) => {
+        #[test]
+        fn $name() {
+            let mut dev = $create_method(&[]);
+            let mut buf = [0; 1];
+            assert_feature_not_available!(dev.read_eeprom(0, &mut buf));
+            assert_feature_not_available!(dev.write_eeprom(0, &[1]));
+            $destroy_method(dev);
+        }
+    };
+}
+
+no_eeprom_test!(mcp7940m_has_no_eeprom, new_mcp7940m, destroy_mcp7940m);
+no_eeprom_test!(mcp7940n_has_no_eeprom, new_mcp7940n, destroy_mcp7940n);
+no_eeprom_test!(mcp79400_has_no_eeprom, new_mcp79400, destroy_mcp79400);
+
+for_eeprom_ics!(
+    can_save_trim,
+    call_test,
+    save_trim_to_eeprom,
+    [I2cTrans::write(EEPROM_ADDR, vec![0x40, 0xFD])],
+    0x40,
+    (-3)
+);
+
+for_eeprom_ics!(
+    cannot_save_invalid_trim,
+    set_invalid_test,
+    save_trim_to_eeprom,
+    0x40,
+    (-128)
+);
+
+macro_rules! load_trim_test {
+    ($name:ident, $create_method:ident, $destroy_method:ident,
+    $stored:expr, $register:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            let trans = [
+                I2cTrans::write_read(EEPROM_ADDR, vec![0x40], vec![$stored]),
+                I2cTrans::write(DEV_ADDR, vec![Register::OSCTRIM, $register]),
+            ];
+            let mut dev = $create_method(&trans);
+            assert_eq!(
+                $expected,
+                dev.load_and_apply_trim_from_eeprom(0x40).unwrap()
+            );
+            $destroy_method(dev);
+        }
+    };
+}
+
+for_eeprom_ics!(can_load_positive_trim, load_trim_test, 0x05, 0x85, 5);
+for_eeprom_ics!(can_load_negative_trim, load_trim_test, 0xFD, 0x03, (-3));
+
+#[test]
+fn trim_round_trip() {
+    let trans = [
+        I2cTrans::write(EEPROM_ADDR, vec![0x7F, 0xE2]),
+        I2cTrans::write_read(EEPROM_ADDR, vec![0x7F], vec![0xE2]),
+        I2cTrans::write(DEV_ADDR, vec![Register::OSCTRIM, 0x1E]),
+    ];
+    let mut dev = new_mcp79411(&trans);
+    dev.save_trim_to_eeprom(0x7F, -30).unwrap();
+    assert_eq!(-30, dev.load_and_apply_trim_from_eeprom(0x7F).unwrap());
+    destroy_mcp79411(dev);
+}
+
+#[test]
+fn cannot_load_invalid_trim() {
+    let trans = [I2cTrans::write_read(EEPROM_ADDR, vec![0x40], vec![0x80])];
+    let mut dev = new_mcp79410(&trans);
+    assert_invalid_input_data!(dev.load_and_apply_trim_from_eeprom(0x40));
+    destroy_mcp79410(dev);
+}
+
+macro_rules! no_eeprom_trim_test {
+    ($name:ident, $create_method:ident, $destroy_method:ident) => {
+        #[test]
+        fn $name() {
+            let mut dev = $create_method(&[]);
+            assert_feature_not_available!(dev.save_trim_to_eeprom(0, 1));
+            assert_feature_not_available!(dev.load_and_apply_trim_from_eeprom(0));
+            $destroy_method(dev);
+        }
+    };
+}
+
+no_eeprom_trim_test!(mcp7940m_cannot_store_trim, new_mcp7940m, destroy_mcp7940m);
+no_eeprom_trim_test!(mcp7940n_cannot_store_trim, new_mcp7940n, destroy_mcp7940n);
+no_eeprom_trim_test!(mcp79400_cannot_store_trim, new_mcp79400, destroy_mcp79400);
+
+macro_rules! eeprom_protected_test {
+    ($name:ident, $create_method:ident, $destroy_method:ident, $status:expr, $expected:expr) => {
+        get_test!(
+            $name,
+            $create_method,
+            $destroy_method,
+            is_eeprom_protected,
+            [I2cTrans::write_read(EEPROM_ADDR, vec![0xFF], vec![$status])],
+            $expected
+        );
+    };
+}
+
+for_eeprom_ics!(not_protected, eeprom_protected_test, 0b0000_0000, false);
+for_eeprom_ics!(
+    upper_quarter_protected,
+    eeprom_protected_test,
+    0b0000_0100,
+    true
+);
+for_eeprom_ics!(
+    upper_half_protected,
+    eeprom_protected_test,
+    0b0000_1000,
+    true
+);
+for_eeprom_ics!(all_protected, eeprom_protected_test, 0b0000_1100, true);
+for_eeprom_ics!(
+    other_bits_ignored,
+    eeprom_protected_test,
+    0b1111_0011,
+    false
+);
+
+macro_rules! no_eeprom_protection_test {
+    ($name:ident, $create_method:ident, $destroy_method:ident) => {
+        #[test]
+        fn $name() {
+            let mut dev = $create_method(&[]);
+            assert_feature_not_available!(dev.is_eeprom_protected());
+            $destroy_method(dev);
+        }
+    };
+}
+
+no_eeprom_protection_test!(mcp7940m_has_no_protection, new_mcp7940m, destroy_mcp7940m);
+no_eeprom_protection_test!(mcp7940n_has_no_protection, new_mcp7940n, destroy_mcp7940n);
+no_eeprom_protection_test!(mcp79400_has_no_protection, new_mcp79400, destroy_mcp79400);
+
+mod detect_variant {
+    use super::*;
+
+    const EUI_ADDR: u8 = 0xF0;
+
+    macro_rules! detect_test {
+        ($name:ident, $eui:expr, $expected:ident) => {
+            #[test]
+            fn $name() {
+                let trans = [
+                    I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![0]),
+                    I2cTrans::write_read(EEPROM_ADDR, vec![EUI_ADDR], $eui.to_vec()),
+                ];
+                let mut dev = new_mcp7940n(&trans);
+                assert_eq!(DetectedVariant::$expected, dev.detect_variant().unwrap());
+                destroy_mcp7940n(dev);
+            }
+        };
+    }
+
+    detect_test!(without_eui, [0xFF; 8], Mcp7941x);
+    detect_test!(
+        with_eui48,
+        [0xFF, 0xFF, 0x00, 0x04, 0xA3, 0x12, 0x34, 0x56],
+        Eui48
+    );
+    detect_test!(
+        with_eui64,
+        [0x00, 0x04, 0xA3, 0xFF, 0xFE, 0x12, 0x34, 0x56],
+        Eui64
+    );
+
+    // Bus where only the addresses in `acked` are acknowledged
+    struct ProbeI2c {
+        acked: &'static [u8],
+    }
+
+    impl embedded_hal::blocking::i2c::WriteRead for ProbeI2c {
+        type Error = ();
+
+        fn write_read(&mut self, address: u8, _bytes: &[u8], buffer: &mut [u8]) -> Result<(), ()> {
+            if !self.acked.contains(&address) {
+                return Err(());
+            }
+            for byte in buffer.iter_mut() {
+                *byte = 0xFF;
+            }
+            Ok(())
+        }
+    }
+
+    impl embedded_hal::blocking::i2c::Write for ProbeI2c {
+        type Error = ();
+
+        fn write(&mut self, _address: u8, _bytes: &[u8]) -> Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn eeprom_address_nak() {
+        let mut dev = Mcp794xx::new_mcp79410(ProbeI2c { acked: &[DEV_ADDR] });
+        assert_eq!(DetectedVariant::Mcp7940x, dev.detect_variant().unwrap());
+    }
+
+    #[test]
+    fn eeprom_address_ack() {
+        let acked = &[DEV_ADDR, EEPROM_ADDR];
+        let mut dev = Mcp794xx::new_mcp7940n(ProbeI2c { acked });
+        assert_eq!(DetectedVariant::Mcp7941x, dev.detect_variant().unwrap());
+    }
+
+    #[test]
+    fn device_nak_is_error() {
+        let mut dev = Mcp794xx::new_mcp79410(ProbeI2c { acked: &[] });
+        match dev.detect_variant() {
+            Err(Error::Comm(())) => (),
+            _ => panic!("Comm error not returned."),
+        }
+    }
+}
+
+mod eui {
+    use super::*;
+
+    const EUI48: [u8; 6] = [0x00, 0x04, 0xA3, 0x12, 0x34, 0x56];
+    const EUI64: [u8; 8] = [0x00, 0x04, 0xA3, 0xFF, 0xFE, 0x12, 0x34, 0x56];
+
+    get_test!(
+        can_read_eui48_mcp79401,
+        new_mcp79401,
+        destroy_mcp79401,
+        read_eui48,
+        [I2cTrans::write_read(
+            EEPROM_ADDR,
+            vec![0xF2],
+            EUI48.to_vec()
+        )],
+        EUI48
+    );
+    get_test!(
+        can_read_eui48_mcp79411,
+        new_mcp79411,
+        destroy_mcp79411,
+        read_eui48,
+        [I2cTrans::write_read(
+            EEPROM_ADDR,
+            vec![0xF2],
+            EUI48.to_vec()
+        )],
+        EUI48
+    );
+    get_test!(
+        can_read_eui64_mcp79402,
+        new_mcp79402,
+        destroy_mcp79402,
+        read_eui64,
+        [I2cTrans::write_read(
+            EEPROM_ADDR,
+            vec![0xF0],
+            EUI64.to_vec()
+        )],
+        EUI64
+    );
+    get_test!(
+        can_read_eui64_mcp79412,
+        new_mcp79412,
+        destroy_mcp79412,
+        read_eui64,
+        [I2cTrans::write_read(
+            EEPROM_ADDR,
+            vec![0xF0],
+            EUI64.to_vec()
+        )],
+        EUI64
+    );
+
+    macro_rules! write_eui_test {
+        ($name:ident, $create_method:ident, $destroy_method:ident, $address:expr, $eui:expr) => {
+            #[test]
+            fn $name() {
+                let trans = [
+                    I2cTrans::write(DEV_ADDR, vec![Register::EEUNLOCK, 0x55]),
+                    I2cTrans::write(DEV_ADDR, vec![Register::EEUNLOCK, 0xAA]),
+                    I2cTrans::write(EEPROM_ADDR, [&[$address][..], &$eui].concat()),
+                ];
+                let mut dev = $create_method(&trans);
+                dev.write_eui(&$eui).unwrap();
+                $destroy_method(dev);
+            }
+        };
+    }
+
+    write_eui_test!(
+        can_write_eui48_mcp79400,
+        new_mcp79400,
+        destroy_mcp79400,
+        0xF2,
+        EUI48
+    );
+    write_eui_test!(
+        can_write_eui64_mcp79400,
+        new_mcp79400,
+        destroy_mcp79400,
+        0xF0,
+        EUI64
+    );
+    write_eui_test!(
+        can_write_eui48_mcp79401,
+        new_mcp79401,
+        destroy_mcp79401,
+        0xF2,
+        EUI48
+    );
+    write_eui_test!(
+        can_write_eui64_mcp79412,
+        new_mcp79412,
+        destroy_mcp79412,
+        0xF0,
+        EUI64
+    );
+
+    write_eui_test!(
+        can_write_eui64_mcp79410,
+        new_mcp79410,
+        destroy_mcp79410,
+        0xF0,
+        EUI64
+    );
+
+    #[test]
+    fn cannot_write_eui64_on_eui48_variants() {
+        let mut dev = new_mcp79401(&[]);
+        assert_invalid_input_data!(dev.write_eui(&EUI64));
+        destroy_mcp79401(dev);
+        let mut dev = new_mcp79411(&[]);
+        assert_invalid_input_data!(dev.write_eui(&EUI64));
+        destroy_mcp79411(dev);
+    }
+
+    #[test]
+    fn cannot_write_eui48_on_eui64_variants() {
+        let mut dev = new_mcp79402(&[]);
+        assert_invalid_input_data!(dev.write_eui(&EUI48));
+        destroy_mcp79402(dev);
+        let mut dev = new_mcp79412(&[]);
+        assert_invalid_input_data!(dev.write_eui(&EUI48));
+        destroy_mcp79412(dev);
+    }
+
+    #[test]
+    fn cannot_write_eui_with_wrong_length() {
+        let mut dev = new_mcp79400(&[]);
+        assert_invalid_input_data!(dev.write_eui(&[]));
+        assert_invalid_input_data!(dev.write_eui(&[0; 7]));
+        assert_invalid_input_data!(dev.write_eui(&[0; 9]));
+        destroy_mcp79400(dev);
+    }
+}
@@ -0,0 +1,187 @@
+extern crate embedded_hal_mock as hal;
+use hal::i2c::Transaction as I2cTrans;
+mod common;
+use common::{
+    destroy_mcp79400, destroy_mcp79401, destroy_mcp79402, destroy_mcp7940m, destroy_mcp7940n,
+    destroy_mcp79410, destroy_mcp79411, destroy_mcp79412, new_mcp79400, new_mcp79401, new_mcp79402,
+    new_mcp7940m, new_mcp7940n, new_mcp79410, new_mcp79411, new_mcp79412, BitFlags, Register,
+    DEVICE_ADDRESS as DEV_ADDR,
+};
+extern crate mcp794xx;
+use mcp794xx::{Error, Hours, PowerFailDateTime};
+
+macro_rules! outage_test {
+    ($name:ident, $year:expr, [ $( $timestamps:expr ),+ ], $expected:expr) => {
+        for_battery_ics!(
+            $name,
+            get_test,
+            power_outage_duration,
+            [
+                I2cTrans::write_read(
+                    DEV_ADDR,
+                    vec![Register::WEEKDAY],
+                    vec![BitFlags::PWRFAIL | BitFlags::VBATEN | 3]
+                ),
+                I2cTrans::write_read(DEV_ADDR, vec![Register::PWRDNMIN], vec![$( $timestamps ),*]),
+                I2cTrans::write_read(DEV_ADDR, vec![Register::YEAR], vec![$year])
+            ],
+            $expected
+        );
+    };
+}
+
+for_battery_ics!(
+    no_outage_without_power_fail,
+    get_test,
+    power_outage_duration,
+    [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::WEEKDAY],
+        vec![BitFlags::VBATEN | 3]
+    )],
+    None
+);
+
+// 31st March 23:58 -> 1st April 00:05
+outage_test!(
+    short_outage,
+    0x19,
+    [0x58, 0x23, 0x31, 0b1110_0011, 0x05, 0x00, 0x01, 0b0010_0100],
+    Some(7 * 60)
+);
+
+// 28th February 11:00 PM -> 1st March 1:00 AM (leap year)
+outage_test!(
+    short_outage_in_leap_year,
+    0x20,
+    [
+        0x00,
+        0b0111_0001,
+        0x28,
+        0b0100_0010,
+        0x00,
+        0b0100_0001,
+        0x01,
+        0b0110_0011
+    ],
+    Some(26 * 3600)
+);
+
+// 31st December 23:00 -> 1st January 01:00
+outage_test!(
+    outage_across_year_boundary,
+    0x19,
+    [0x00, 0x23, 0x31, 0b0011_0010, 0x00, 0x01, 0x01, 0b0100_0001],
+    Some(2 * 3600)
+);
+
+// 15th January -> 20th March: unknown number of years in between
+outage_test!(
+    ambiguous_outage,
+    0x19,
+    [0x00, 0x12, 0x15, 0b0010_0001, 0x00, 0x12, 0x20, 0b0010_0011],
+    None
+);
+
+// Timestamps cleared
+outage_test!(invalid_timestamps, 0x19, [0, 0, 0, 0, 0, 0, 0, 0], None);
+
+for_battery_ics!(
+    can_get_timestamps,
+    get_test,
+    get_power_fail_timestamps,
+    [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::PWRDNMIN],
+        vec![
+            0x59,
+            0x23,
+            0x31,
+            0b0101_0010,
+            0x05,
+            0b0100_0011,
+            0x01,
+            0b0110_0001
+        ]
+    )],
+    (
+        PowerFailDateTime {
+            month: 12,
+            day: 31,
+            weekday: 2,
+            hour: Hours::H24(23),
+            minute: 59,
+        },
+        PowerFailDateTime {
+            month: 1,
+            day: 1,
+            weekday: 3,
+            hour: Hours::AM(3),
+            minute: 5,
+        }
+    )
+);
+
+macro_rules! get_timestamp_test {
+    ($name:ident, $method:ident, $register:ident, [ $( $data:expr ),+ ], $expected:expr) => {
+        for_battery_ics!(
+            $name,
+            get_test,
+            $method,
+            [I2cTrans::write_read(DEV_ADDR, vec![Register::$register], vec![$( $data ),*])],
+            $expected
+        );
+    };
+}
+
+get_timestamp_test!(
+    can_get_power_down_datetime_24h,
+    get_power_down_datetime,
+    PWRDNMIN,
+    [0x59, 0x23, 0x31, 0b0101_0010],
+    PowerFailDateTime {
+        month: 12,
+        day: 31,
+        weekday: 2,
+        hour: Hours::H24(23),
+        minute: 59,
+    }
+);
+
+get_timestamp_test!(
+    can_get_power_down_datetime_12h,
+    get_power_down_datetime,
+    PWRDNMIN,
+    [0x30, 0b0111_0001, 0x28, 0b1110_0010],
+    PowerFailDateTime {
+        month: 2,
+        day: 28,
+        weekday: 7,
+        hour: Hours::PM(11),
+        minute: 30,
+    }
+);
+
+get_timestamp_test!(
+    can_get_power_up_datetime,
+    get_power_up_datetime,
+    PWRUPMIN,
+    [0x05, 0b0100_0011, 0x01, 0b0110_0001],
+    PowerFailDateTime {
+        month: 1,
+        day: 1,
+        weekday: 3,
+        hour: Hours::AM(3),
+        minute: 5,
+    }
+);
+
+#[test]
+fn mcp7940m_has_no_power_fail_timestamps() {
+    let mut dev = new_mcp7940m(&[]);
+    assert_feature_not_available!(dev.power_outage_duration());
+    assert_feature_not_available!(dev.get_power_fail_timestamps());
+    assert_feature_not_available!(dev.get_power_down_datetime());
+    assert_feature_not_available!(dev.get_power_up_datetime());
+    destroy_mcp7940m(dev);
+}
@@ -0,0 +1,330 @@
+extern crate embedded_hal_mock as hal;
+use hal::i2c::Transaction as I2cTrans;
+mod common;
+use common::{
+    destroy_mcp79400, destroy_mcp79401, destroy_mcp79402, destroy_mcp7940m, destroy_mcp7940n,
+    destroy_mcp79410, destroy_mcp79411, destroy_mcp79412, new_mcp79400, new_mcp79401, new_mcp79402,
+    new_mcp7940m, new_mcp7940n, new_mcp79410, new_mcp79411, new_mcp79412, BitFlags, Register,
+    DEVICE_ADDRESS as DEV_ADDR,
+};
+extern crate embedded_hal;
+extern crate mcp794xx;
+use mcp794xx::{
+    AlarmDateTime, AlarmMatching, DateTime, Error, FullConfig, Hours, Mcp794xx, OutputPolarity,
+    SchedulingConfig, SqWaveFreq,
+};
+
+const EEUNLOCK: usize = 0x09;
+const RESERVED: usize = 0x10;
+
+fn new_config() -> FullConfig {
+    FullConfig {
+        datetime: Some(DateTime {
+            year: 2018,
+            month: 8,
+            day: 13,
+            weekday: 1,
+            hour: Hours::H24(23),
+            minute: 59,
+            second: 58,
+        }),
+        alarm0: (
+            AlarmDateTime {
+                month: 3,
+                day: 4,
+                weekday: 1,
+                hour: Hours::H24(7),
+                minute: 30,
+                second: 10,
+            },
+            AlarmMatching::AllMatch,
+        ),
+        alarm1: (
+            AlarmDateTime {
+                month: 12,
+                day: 25,
+                weekday: 3,
+                hour: Hours::H24(12),
+                minute: 45,
+                second: 15,
+            },
+            AlarmMatching::HoursMatch,
+        ),
+        alarm_polarity: OutputPolarity::High,
+        trim: 0x05,
+        control: BitFlags::OUT,
+    }
+}
+
+// Registers RTCSEC to ALM1MTH corresponding to `new_config()`
+fn config_registers() -> Vec<u8> {
+    vec![
+        0x58,
+        0x59,
+        0x23,
+        0x01,
+        0x13,
+        0x08,
+        0x18,
+        BitFlags::OUT,
+        0x05,
+        0,
+        0x10,
+        0x30,
+        0x07,
+        BitFlags::ALMPOL | 0b0111_0001,
+        0x04,
+        0x03,
+        0,
+        0x15,
+        0x45,
+        0x12,
+        0b0010_0011,
+        0x25,
+        0x12,
+    ]
+}
+
+// `config_registers()` with the bits not covered by the configuration set
+fn device_registers() -> Vec<u8> {
+    let mut registers = config_registers();
+    registers[Register::SECONDS as usize] |= BitFlags::ST;
+    registers[Register::WEEKDAY as usize] |= BitFlags::OSCRUN | BitFlags::VBATEN;
+    registers[Register::ALM0WKDAY as usize] |= BitFlags::ALMIF;
+    registers
+}
+
+fn read_snapshot(registers: Vec<u8>) -> I2cTrans {
+    I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], registers)
+}
+
+for_all_ics!(
+    nothing_differs,
+    get_test,
+    reconcile,
+    [read_snapshot(device_registers())],
+    0,
+    (&new_config())
+);
+
+fn some_differ_config() -> FullConfig {
+    let mut config = new_config();
+    config.datetime = None;
+    config.control = BitFlags::SQWEN;
+    config.alarm1.0.minute = 46;
+    config
+}
+
+fn some_differ_registers() -> Vec<u8> {
+    let mut registers = device_registers();
+    registers[Register::SECONDS as usize] = BitFlags::ST | 0x30;
+    registers
+}
+
+// The time-keeping registers are not compared if no date and time is given.
+for_all_ics!(
+    some_differ,
+    get_test,
+    reconcile,
+    [
+        read_snapshot(some_differ_registers()),
+        I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, BitFlags::SQWEN]),
+        I2cTrans::write(DEV_ADDR, vec![Register::ALM1SEC + 1, 0x46])
+    ],
+    2,
+    (&some_differ_config())
+);
+
+fn all_differ_transactions() -> Vec<I2cTrans> {
+    let mut transactions = vec![read_snapshot(vec![0; 23])];
+    for (address, value) in config_registers().into_iter().enumerate() {
+        if address != EEUNLOCK && address != RESERVED {
+            transactions.push(I2cTrans::write(DEV_ADDR, vec![address as u8, value]));
+        }
+    }
+    transactions
+}
+
+macro_rules! all_differ_test {
+    ($name:ident, $create_method:ident, $destroy_method:ident, $expected:expr) => {
+        #[test]
+        fn $name() {
+            let mut dev = $create_method(&all_differ_transactions());
+            assert_eq!($expected, dev.reconcile(&new_config()).unwrap());
+            $destroy_method(dev);
+        }
+    };
+}
+
+for_all_ics!(all_differ, all_differ_test, 21);
+
+fn invalid_config() -> FullConfig {
+    let mut config = new_config();
+    config.alarm0.0.month = 13;
+    config
+}
+
+for_all_ics!(
+    cannot_reconcile_invalid_config,
+    set_invalid_test,
+    reconcile,
+    (&invalid_config())
+);
+
+mod scheduling_config {
+    use super::*;
+
+    // Registers CONTROL to ALM1MTH
+    fn current_registers() -> Vec<u8> {
+        vec![
+            BitFlags::OUT | BitFlags::ALM1EN | BitFlags::SQWFS,
+            0x05,
+            0,
+            0,
+            0,
+            0,
+            BitFlags::ALMPOL | BitFlags::ALMIF | 1,
+            1,
+            1,
+            0,
+            0,
+            0,
+            0,
+            BitFlags::ALMIF | 1,
+            1,
+            1,
+        ]
+    }
+
+    fn new_scheduling_config() -> SchedulingConfig {
+        SchedulingConfig {
+            alarm0: Some(new_config().alarm0),
+            alarm1: None,
+            square_wave: Some(SqWaveFreq::_4_096Hz),
+        }
+    }
+
+    for_all_ics!(
+        can_apply,
+        call_test,
+        apply_scheduling_config,
+        [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], current_registers()),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![
+                    Register::ALM0SEC,
+                    0x10,
+                    0x30,
+                    0x07,
+                    BitFlags::ALMPOL | 0b0111_0001,
+                    0x04,
+                    0x03
+                ]
+            ),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![
+                    Register::CONTROL,
+                    BitFlags::OUT | BitFlags::SQWEN | BitFlags::ALM0EN | 0b01
+                ]
+            )
+        ],
+        (&new_scheduling_config())
+    );
+
+    for_all_ics!(
+        can_disable_all,
+        call_test,
+        apply_scheduling_config,
+        [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::CONTROL], current_registers()),
+            I2cTrans::write(DEV_ADDR, vec![Register::CONTROL, BitFlags::OUT])
+        ],
+        (&SchedulingConfig {
+            alarm0: None,
+            alarm1: None,
+            square_wave: None,
+        })
+    );
+
+    for_all_ics!(
+        cannot_apply_invalid,
+        set_invalid_test,
+        apply_scheduling_config,
+        (&SchedulingConfig {
+            alarm1: Some((
+                AlarmDateTime {
+                    weekday: 0,
+                    ..new_config().alarm1.0
+                },
+                AlarmMatching::AllMatch
+            )),
+            ..new_scheduling_config()
+        })
+    );
+
+    // Register memory where the write number `fail_on` (counting from 0)
+    // is not acknowledged
+    struct FlakyI2c {
+        registers: [u8; 0x20],
+        fail_on: usize,
+        write_count: usize,
+        writes: Vec<Vec<u8>>,
+    }
+
+    impl embedded_hal::blocking::i2c::WriteRead for FlakyI2c {
+        type Error = ();
+
+        fn write_read(&mut self, _address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), ()> {
+            let start = usize::from(bytes[0]);
+            buffer.copy_from_slice(&self.registers[start..start + buffer.len()]);
+            Ok(())
+        }
+    }
+
+    impl embedded_hal::blocking::i2c::Write for FlakyI2c {
+        type Error = ();
+
+        fn write(&mut self, _address: u8, bytes: &[u8]) -> Result<(), ()> {
+            let count = self.write_count;
+            self.write_count += 1;
+            if count == self.fail_on {
+                return Err(());
+            }
+            let start = usize::from(bytes[0]);
+            self.registers[start..start + bytes.len() - 1].copy_from_slice(&bytes[1..]);
+            self.writes.push(bytes.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn restores_on_error() {
+        let mut registers = [0; 0x20];
+        registers[usize::from(Register::CONTROL)..0x17].copy_from_slice(&current_registers());
+        let i2c = FlakyI2c {
+            registers,
+            fail_on: 1,
+            write_count: 0,
+            writes: Vec::new(),
+        };
+        let mut dev = Mcp794xx::new_mcp79410(i2c);
+        let config = SchedulingConfig {
+            alarm1: Some(new_config().alarm1),
+            ..new_scheduling_config()
+        };
+        match dev.apply_scheduling_config(&config) {
+            Err(Error::Comm(())) => (),
+            _ => panic!("Comm error not returned."),
+        }
+        let i2c = dev.destroy_mcp79410();
+        let saved_alarm0 = [&[Register::ALM0SEC][..], &current_registers()[3..9]].concat();
+        let saved_alarm1 = [&[Register::ALM1SEC][..], &current_registers()[10..]].concat();
+        assert_eq!(3, i2c.writes.len());
+        assert_eq!(Register::ALM0SEC, i2c.writes[0][0]);
+        assert_eq!(saved_alarm1, i2c.writes[1]);
+        assert_eq!(saved_alarm0, i2c.writes[2]);
+        assert_eq!(registers[..], i2c.registers[..]);
+    }
+}
@@ -0,0 +1,159 @@
+extern crate embedded_hal_mock as hal;
+use hal::delay::MockNoop;
+use hal::i2c::Transaction as I2cTrans;
+mod common;
+use common::{
+    destroy_mcp79400, destroy_mcp79401, destroy_mcp79402, destroy_mcp7940m, destroy_mcp7940n,
+    destroy_mcp79410, destroy_mcp79411, destroy_mcp79412, new_mcp79400, new_mcp79401, new_mcp79402,
+    new_mcp7940m, new_mcp7940n, new_mcp79410, new_mcp79411, new_mcp79412, BitFlags, Register,
+    DEVICE_ADDRESS as DEV_ADDR, EEPROM_ADDRESS as EEPROM_ADDR,
+};
+extern crate mcp794xx;
+use mcp794xx::SelfTestReport;
+
+const EUI48: [u8; 8] = [0xFF, 0xFF, 0x00, 0x04, 0xA3, 0x12, 0x34, 0x56];
+const EUI64: [u8; 8] = [0x00, 0x04, 0xA3, 0xFF, 0xFE, 0x12, 0x34, 0x56];
+
+macro_rules! self_test {
+    ($name:ident, $create_method:ident, $destroy_method:ident,
+    $sram_readback:expr, $eui:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            let mut trans = vec![
+                I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![0x12]),
+                I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![0x12]),
+                I2cTrans::write(DEV_ADDR, vec![Register::SECONDS, BitFlags::ST | 0x12]),
+                I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![1]),
+                I2cTrans::write_read(
+                    DEV_ADDR,
+                    vec![Register::WEEKDAY],
+                    vec![BitFlags::OSCRUN | 1],
+                ),
+                I2cTrans::write_read(DEV_ADDR, vec![Register::SRAM], vec![0x12, 0x34]),
+                I2cTrans::write(DEV_ADDR, vec![Register::SRAM, 0x55, 0xAA]),
+                I2cTrans::write_read(DEV_ADDR, vec![Register::SRAM], $sram_readback),
+                I2cTrans::write(DEV_ADDR, vec![Register::SRAM, 0x12, 0x34]),
+            ];
+            let eui: Option<[u8; 8]> = $eui;
+            if let Some(eui) = eui {
+                trans.push(I2cTrans::write_read(EEPROM_ADDR, vec![0xF0], eui.to_vec()));
+            }
+            let mut dev = $create_method(&trans);
+            let report = dev.self_test(&mut MockNoop::new()).unwrap();
+            assert_eq!($expected, report);
+            assert_eq!($expected.passed(), report.passed());
+            $destroy_method(dev);
+        }
+    };
+}
+
+// ICs without a preprogrammed EUI
+macro_rules! for_ics_without_eui {
+    ($name:ident, $macroname:ident, $( $args:tt ),*) => {
+        mod $name {
+            use super::*;
+            $macroname!(for_mcp7940m, new_mcp7940m, destroy_mcp7940m, $($args),*);
+            $macroname!(for_mcp7940n, new_mcp7940n, destroy_mcp7940n, $($args),*);
+            $macroname!(for_mcp79400, new_mcp79400, destroy_mcp79400, $($args),*);
+            $macroname!(for_mcp79410, new_mcp79410, destroy_mcp79410, $($args),*);
+        }
+    };
+}
+
+const ALL_PASS: SelfTestReport = SelfTestReport {
+    device_responds: true,
+    oscillator_running: true,
+    sram_ok: true,
+    eui_ok: None,
+};
+
+for_ics_without_eui!(all_pass, self_test, (vec![0x55, 0xAA]), None, ALL_PASS);
+
+for_ics_without_eui!(
+    sram_readback_fails,
+    self_test,
+    (vec![0x55, 0xAB]),
+    None,
+    (SelfTestReport {
+        sram_ok: false,
+        ..ALL_PASS
+    })
+);
+
+macro_rules! eui_test {
+    ($name:ident, $create_method:ident, $destroy_method:ident, $eui:expr, $eui_ok:expr) => {
+        self_test!(
+            $name,
+            $create_method,
+            $destroy_method,
+            vec![0x55, 0xAA],
+            Some($eui),
+            SelfTestReport {
+                eui_ok: Some($eui_ok),
+                ..ALL_PASS
+            }
+        );
+    };
+}
+
+eui_test!(
+    eui48_pass_mcp79401,
+    new_mcp79401,
+    destroy_mcp79401,
+    EUI48,
+    true
+);
+eui_test!(
+    eui48_pass_mcp79411,
+    new_mcp79411,
+    destroy_mcp79411,
+    EUI48,
+    true
+);
+eui_test!(
+    eui64_pass_mcp79402,
+    new_mcp79402,
+    destroy_mcp79402,
+    EUI64,
+    true
+);
+eui_test!(
+    eui64_pass_mcp79412,
+    new_mcp79412,
+    destroy_mcp79412,
+    EUI64,
+    true
+);
+eui_test!(
+    erased_eui48_fails,
+    new_mcp79411,
+    destroy_mcp79411,
+    [0x00, 0x04, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
+    false
+);
+eui_test!(
+    erased_eui64_fails,
+    new_mcp79412,
+    destroy_mcp79412,
+    [0xFF; 8],
+    false
+);
+
+#[test]
+fn report_passed_only_if_all_checks_pass() {
+    let report = SelfTestReport {
+        oscillator_running: false,
+        ..ALL_PASS
+    };
+    assert!(!report.passed());
+    let report = SelfTestReport {
+        eui_ok: Some(false),
+        ..ALL_PASS
+    };
+    assert!(!report.passed());
+    let report = SelfTestReport {
+        eui_ok: Some(true),
+        ..ALL_PASS
+    };
+    assert!(report.passed());
+}
@@ -0,0 +1,566 @@
+extern crate embedded_hal_mock as hal;
+use hal::delay::MockNoop;
+use hal::i2c::Transaction as I2cTrans;
+mod common;
+use common::{
+    destroy_mcp79400, destroy_mcp79401, destroy_mcp79402, destroy_mcp7940m, destroy_mcp7940n,
+    destroy_mcp79410, destroy_mcp79411, destroy_mcp79412, new_mcp79400, new_mcp79401, new_mcp79402,
+    new_mcp7940m, new_mcp7940n, new_mcp79410, new_mcp79411, new_mcp79412, BitFlags, Register,
+    DEVICE_ADDRESS as DEV_ADDR,
+};
+extern crate mcp794xx;
+use mcp794xx::{DateTime, Error, HourFormat, Hours, Rtcc, SramRegion, RESERVED_SRAM_START};
+
+const BOOT_TIME_ADDR: u8 = Register::SRAM + 60;
+const PROVISIONING_TIME_ADDR: u8 = Register::SRAM + 56;
+const TRIM_RECORD_ADDR: u8 = Register::SRAM + 54;
+const OSC_STARTED_ADDR: u8 = Register::SRAM + 53;
+
+#[test]
+fn driver_records_start_at_reserved_offset() {
+    let first = [
+        BOOT_TIME_ADDR,
+        PROVISIONING_TIME_ADDR,
+        TRIM_RECORD_ADDR,
+        OSC_STARTED_ADDR,
+    ]
+    .iter()
+    .min()
+    .cloned();
+    assert_eq!(Some(Register::SRAM + RESERVED_SRAM_START), first);
+}
+
+// 2019-01-01T00:00:00
+const BOOT_TIME: [u8; 7] = [BitFlags::ST, 0, 0, 2, 1, 1, 0x19];
+const BOOT_TIME_SECONDS: [u8; 4] = [0x23, 0xBD, 0x6A, 0x00];
+
+for_all_ics!(
+    can_store_boot_time,
+    call_test,
+    store_boot_time,
+    [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], BOOT_TIME.to_vec()),
+        I2cTrans::write(
+            DEV_ADDR,
+            [&[BOOT_TIME_ADDR][..], &BOOT_TIME_SECONDS].concat()
+        )
+    ]
+);
+
+#[test]
+fn cannot_store_boot_time_past_timestamp_range() {
+    let trans = [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::SECONDS],
+        BOOT_TIME.to_vec(),
+    )];
+    let mut dev = new_mcp7940n(&trans);
+    dev.set_century_base(2200).unwrap();
+    assert_invalid_input_data!(dev.store_boot_time());
+    destroy_mcp7940n(dev);
+}
+
+for_all_ics!(
+    can_get_uptime,
+    get_test,
+    uptime_seconds,
+    [
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::SECONDS],
+            vec![BitFlags::ST | 0x03, 0x02, 0x01, 2, 1, 1, 0x19]
+        ),
+        I2cTrans::write_read(DEV_ADDR, vec![BOOT_TIME_ADDR], BOOT_TIME_SECONDS.to_vec())
+    ],
+    3723
+);
+
+for_all_ics!(
+    uptime_is_zero_if_boot_time_in_future,
+    get_test,
+    uptime_seconds,
+    [
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::SECONDS],
+            vec![BitFlags::ST, 0, 0, 1, 0x31, 0x12, 0x18]
+        ),
+        I2cTrans::write_read(DEV_ADDR, vec![BOOT_TIME_ADDR], BOOT_TIME_SECONDS.to_vec())
+    ],
+    0
+);
+
+for_all_ics!(
+    writes_changed_sram_byte,
+    get_test,
+    write_sram_byte_if_changed,
+    [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::SRAM + 5], vec![0xAB]),
+        I2cTrans::write(DEV_ADDR, vec![Register::SRAM + 5, 0xCD])
+    ],
+    true,
+    5,
+    0xCD
+);
+
+for_all_ics!(
+    skips_unchanged_sram_byte,
+    get_test,
+    write_sram_byte_if_changed,
+    [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::SRAM + 63],
+        vec![0xCD]
+    )],
+    false,
+    63,
+    0xCD
+);
+
+#[test]
+fn cannot_write_sram_byte_if_changed_out_of_bounds() {
+    let mut dev = new_mcp7940n(&[]);
+    assert_invalid_input_data!(dev.write_sram_byte_if_changed(64, 0));
+    destroy_mcp7940n(dev);
+}
+
+for_all_ics!(
+    can_set_datetime_and_log_provisioning,
+    call_test,
+    set_datetime_and_log_provisioning,
+    [
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::SECONDS],
+            vec![BitFlags::ST, 0, 0, BitFlags::VBATEN | 1, 1, 1, 0]
+        ),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![
+                Register::SECONDS,
+                BitFlags::ST,
+                0,
+                0,
+                BitFlags::VBATEN | 2,
+                1,
+                1,
+                0x19
+            ]
+        ),
+        I2cTrans::write(
+            DEV_ADDR,
+            [&[PROVISIONING_TIME_ADDR][..], &BOOT_TIME_SECONDS].concat()
+        )
+    ],
+    (&DateTime {
+        year: 2019,
+        month: 1,
+        day: 1,
+        weekday: 2,
+        hour: Hours::H24(0),
+        minute: 0,
+        second: 0,
+    })
+);
+
+#[test]
+fn cannot_log_provisioning_of_invalid_datetime() {
+    let mut dev = new_mcp7940n(&[]);
+    let datetime = DateTime {
+        year: 2019,
+        month: 13,
+        day: 1,
+        weekday: 2,
+        hour: Hours::H24(0),
+        minute: 0,
+        second: 0,
+    };
+    assert_invalid_input_data!(dev.set_datetime_and_log_provisioning(&datetime));
+    destroy_mcp7940n(dev);
+}
+
+#[test]
+fn cannot_log_provisioning_past_timestamp_range() {
+    let mut dev = new_mcp7940n(&[]);
+    dev.set_century_base(2100).unwrap();
+    let datetime = DateTime {
+        year: 2137,
+        month: 1,
+        day: 1,
+        weekday: 2,
+        hour: Hours::H24(0),
+        minute: 0,
+        second: 0,
+    };
+    assert_invalid_input_data!(dev.set_datetime_and_log_provisioning(&datetime));
+    destroy_mcp7940n(dev);
+}
+
+for_all_ics!(
+    can_store_applied_trim,
+    call_test,
+    store_applied_trim,
+    [I2cTrans::write(
+        DEV_ADDR,
+        vec![TRIM_RECORD_ADDR, 0xA5, 0xF6]
+    )],
+    (-10)
+);
+
+for_all_ics!(
+    can_get_last_applied_trim,
+    get_test,
+    get_last_applied_trim,
+    [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![TRIM_RECORD_ADDR],
+        vec![0xA5, 0x0C]
+    )],
+    (Some(12))
+);
+
+for_all_ics!(
+    no_last_applied_trim,
+    get_test,
+    get_last_applied_trim,
+    [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![TRIM_RECORD_ADDR],
+        vec![0, 0]
+    )],
+    None
+);
+
+#[test]
+fn can_round_trip_applied_trim() {
+    let trans = [
+        I2cTrans::write(DEV_ADDR, vec![TRIM_RECORD_ADDR, 0xA5, 0x80]),
+        I2cTrans::write_read(DEV_ADDR, vec![TRIM_RECORD_ADDR], vec![0xA5, 0x80]),
+    ];
+    let mut dev = new_mcp79410(&trans);
+    dev.store_applied_trim(-128).unwrap();
+    assert_eq!(Some(-128), dev.get_last_applied_trim().unwrap());
+    destroy_mcp79410(dev);
+}
+
+type Config = SramRegion<8, 4>;
+type LastByte = SramRegion<63, 1>;
+
+#[test]
+fn can_read_sram_region() {
+    let trans = [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::SRAM + 8],
+        vec![1, 2, 3, 4],
+    )];
+    let mut dev = new_mcp79410(&trans);
+    let mut buf = [0; 4];
+    Config::read(&mut dev, &mut buf).unwrap();
+    assert_eq!([1, 2, 3, 4], buf);
+    destroy_mcp79410(dev);
+}
+
+#[test]
+fn can_write_sram_region() {
+    let trans = [I2cTrans::write(DEV_ADDR, vec![Register::SRAM + 63, 0xAB])];
+    let mut dev = new_mcp7940n(&trans);
+    LastByte::write(&mut dev, &[0xAB]).unwrap();
+    destroy_mcp7940n(dev);
+}
+
+#[test]
+fn cannot_access_sram_region_with_wrong_length() {
+    let mut dev = new_mcp7940n(&[]);
+    let mut buf = [0; 3];
+    assert_invalid_input_data!(Config::read(&mut dev, &mut buf));
+    assert_invalid_input_data!(Config::write(&mut dev, &[0; 5]));
+    destroy_mcp7940n(dev);
+}
+
+mod oscillator_has_started {
+    use super::*;
+
+    for_all_ics!(
+        never_started,
+        get_test,
+        oscillator_has_started,
+        [
+            I2cTrans::write_read(DEV_ADDR, vec![OSC_STARTED_ADDR], vec![0]),
+            I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![1])
+        ],
+        false
+    );
+
+    for_all_ics!(
+        sets_flag_when_running,
+        get_test,
+        oscillator_has_started,
+        [
+            I2cTrans::write_read(DEV_ADDR, vec![OSC_STARTED_ADDR], vec![0]),
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::WEEKDAY],
+                vec![BitFlags::OSCRUN | 1]
+            ),
+            I2cTrans::write(DEV_ADDR, vec![OSC_STARTED_ADDR, 0x5A])
+        ],
+        true
+    );
+
+    for_all_ics!(
+        started_but_currently_stopped,
+        get_test,
+        oscillator_has_started,
+        [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![OSC_STARTED_ADDR],
+            vec![0x5A]
+        )],
+        true
+    );
+}
+
+mod sram_access {
+    use super::*;
+
+    macro_rules! read_sram_test {
+        ($name:ident, $create_method:ident, $destroy_method:ident, $offset:expr) => {
+            #[test]
+            fn $name() {
+                let trans = [I2cTrans::write_read(
+                    DEV_ADDR,
+                    vec![Register::SRAM + $offset],
+                    vec![0xAB, 0xCD, 0xEF],
+                )];
+                let mut dev = $create_method(&trans);
+                let mut buf = [0; 3];
+                dev.read_sram($offset, &mut buf).unwrap();
+                assert_eq!([0xAB, 0xCD, 0xEF], buf);
+                $destroy_method(dev);
+            }
+        };
+    }
+
+    for_all_ics!(can_read_start, read_sram_test, 0);
+    for_all_ics!(can_read_end, read_sram_test, 61);
+
+    for_all_ics!(
+        can_write_start,
+        call_test,
+        write_sram,
+        [I2cTrans::write(DEV_ADDR, vec![Register::SRAM, 1, 2, 3])],
+        0,
+        (&[1, 2, 3])
+    );
+
+    for_all_ics!(
+        can_write_whole_sram,
+        call_test,
+        write_sram,
+        [I2cTrans::write(
+            DEV_ADDR,
+            [&[Register::SRAM][..], &[0x5A; 64]].concat()
+        )],
+        0,
+        (&[0x5A; 64])
+    );
+
+    for_all_ics!(
+        cannot_write_past_end,
+        set_invalid_test,
+        write_sram,
+        62,
+        (&[1, 2, 3])
+    );
+
+    for_all_ics!(
+        cannot_write_offset_out_of_range,
+        set_invalid_test,
+        write_sram,
+        64,
+        (&[1])
+    );
+
+    #[test]
+    fn cannot_read_past_end() {
+        let mut dev = new_mcp7940n(&[]);
+        let mut buf = [0; 2];
+        assert_invalid_input_data!(dev.read_sram(63, &mut buf));
+        assert_invalid_input_data!(dev.read_sram(255, &mut buf));
+        destroy_mcp7940n(dev);
+    }
+}
+
+mod sram_byte {
+    use super::*;
+
+    for_all_ics!(
+        can_read,
+        get_test,
+        read_sram_byte,
+        [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::SRAM + 63],
+            vec![0xAB]
+        )],
+        0xAB,
+        63
+    );
+
+    for_all_ics!(
+        can_write,
+        call_test,
+        write_sram_byte,
+        [I2cTrans::write(DEV_ADDR, vec![Register::SRAM + 5, 0xCD])],
+        5,
+        0xCD
+    );
+
+    for_all_ics!(
+        cannot_read_out_of_range,
+        set_invalid_test,
+        read_sram_byte,
+        64
+    );
+    for_all_ics!(
+        cannot_write_out_of_range,
+        set_invalid_test,
+        write_sram_byte,
+        64,
+        0
+    );
+}
+
+mod valid_marker {
+    use super::*;
+
+    const MARKER_ADDR: u8 = Register::SRAM + 52;
+
+    fn new_datetime() -> DateTime {
+        DateTime {
+            year: 2019,
+            month: 1,
+            day: 1,
+            weekday: 2,
+            hour: Hours::H24(0),
+            minute: 0,
+            second: 0,
+        }
+    }
+
+    #[test]
+    fn set_datetime_writes_marker() {
+        let trans = [
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::SECONDS],
+                vec![BitFlags::ST, 0, 0, 1, 1, 1, 0],
+            ),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![Register::SECONDS, BitFlags::ST, 0, 0, 2, 1, 1, 0x19],
+            ),
+            I2cTrans::write(DEV_ADDR, vec![MARKER_ADDR, 0xC3]),
+        ];
+        let mut dev = new_mcp79410(&trans);
+        dev.set_valid_marker_address(52).unwrap();
+        dev.set_datetime(&new_datetime()).unwrap();
+        destroy_mcp79410(dev);
+    }
+
+    #[test]
+    fn set_datetime_on_pps_writes_marker() {
+        let trans = [
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::SECONDS],
+                vec![BitFlags::ST, 0, 0, 1, 1, 1, 0],
+            ),
+            I2cTrans::write(DEV_ADDR, vec![Register::SECONDS, 0, 0, 0, 2, 1, 1, 0x19]),
+            I2cTrans::write(DEV_ADDR, vec![Register::SECONDS, BitFlags::ST]),
+            I2cTrans::write(DEV_ADDR, vec![MARKER_ADDR, 0xC3]),
+        ];
+        let mut dev = new_mcp79410(&trans);
+        dev.set_valid_marker_address(52).unwrap();
+        dev.set_datetime_on_pps(&new_datetime(), || ()).unwrap();
+        destroy_mcp79410(dev);
+    }
+
+    #[test]
+    fn setup_wall_clock_writes_marker() {
+        let trans = [
+            I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![0, 0, 0, 1, 1, 1, 0]),
+            I2cTrans::write(
+                DEV_ADDR,
+                vec![
+                    Register::SECONDS,
+                    BitFlags::ST,
+                    0,
+                    0,
+                    BitFlags::VBATEN | 2,
+                    1,
+                    1,
+                    0x19,
+                ],
+            ),
+            I2cTrans::write(DEV_ADDR, vec![MARKER_ADDR, 0xC3]),
+            I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::WEEKDAY],
+                vec![BitFlags::OSCRUN | BitFlags::VBATEN | 2],
+            ),
+        ];
+        let mut dev = new_mcp79410(&trans);
+        dev.set_valid_marker_address(52).unwrap();
+        dev.setup_wall_clock(&new_datetime(), HourFormat::H24, &mut MockNoop::new())
+            .unwrap();
+        destroy_mcp79410(dev);
+    }
+
+    macro_rules! has_valid_time_test {
+        ($name:ident, $weekday:expr, $marker:expr, $expected:expr) => {
+            #[test]
+            fn $name() {
+                let mut trans = vec![I2cTrans::write_read(
+                    DEV_ADDR,
+                    vec![Register::WEEKDAY],
+                    vec![$weekday],
+                )];
+                if let Some(marker) = $marker {
+                    trans.push(I2cTrans::write_read(
+                        DEV_ADDR,
+                        vec![MARKER_ADDR],
+                        vec![marker],
+                    ));
+                }
+                let mut dev = new_mcp7940n(&trans);
+                dev.set_valid_marker_address(52).unwrap();
+                assert_eq!($expected, dev.has_valid_time().unwrap());
+                destroy_mcp7940n(dev);
+            }
+        };
+    }
+
+    has_valid_time_test!(marker_present, BitFlags::OSCRUN | 2, Some(0xC3), true);
+    has_valid_time_test!(marker_missing, BitFlags::OSCRUN | 2, Some(0), false);
+    has_valid_time_test!(oscillator_stopped, 2, None::<u8>, false);
+
+    for_all_ics!(
+        without_marker,
+        get_test,
+        has_valid_time,
+        [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::WEEKDAY],
+            vec![BitFlags::OSCRUN | 2]
+        )],
+        true
+    );
+
+    for_all_ics!(
+        cannot_set_invalid_address,
+        set_invalid_test,
+        set_valid_marker_address,
+        64
+    );
+}
@@ -0,0 +1,440 @@
+extern crate embedded_hal_mock as hal;
+use hal::delay::MockNoop;
+use hal::i2c::Transaction as I2cTrans;
+mod common;
+use common::{
+    destroy_mcp79400, destroy_mcp79401, destroy_mcp79402, destroy_mcp7940m, destroy_mcp7940n,
+    destroy_mcp79410, destroy_mcp79411, destroy_mcp79412, new_mcp79400, new_mcp79401, new_mcp79402,
+    new_mcp7940m, new_mcp7940n, new_mcp79410, new_mcp79411, new_mcp79412, BitFlags, Register,
+    DEVICE_ADDRESS as DEV_ADDR,
+};
+extern crate mcp794xx;
+use mcp794xx::{Error, Rtcc, Status};
+
+for_all_ics!(
+    can_get_status,
+    get_test,
+    get_status,
+    [
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::WEEKDAY],
+            vec![BitFlags::OSCRUN | BitFlags::VBATEN | 3]
+        ),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::CONTROL],
+            vec![BitFlags::OUT | BitFlags::ALM1EN | 0b11]
+        )
+    ],
+    (Status {
+        oscillator_running: true,
+        power_failed: false,
+        battery_backup_enabled: true,
+        alarm0_enabled: false,
+        alarm1_enabled: true,
+        square_wave_enabled: false,
+        output_level_high: true,
+    })
+);
+
+for_all_ics!(
+    can_get_status_power_failed,
+    get_test,
+    get_status,
+    [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![BitFlags::PWRFAIL]),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::CONTROL],
+            vec![BitFlags::SQWEN | BitFlags::ALM0EN]
+        )
+    ],
+    (Status {
+        oscillator_running: false,
+        power_failed: true,
+        battery_backup_enabled: false,
+        alarm0_enabled: true,
+        alarm1_enabled: false,
+        square_wave_enabled: true,
+        output_level_high: false,
+    })
+);
+
+macro_rules! debounced_test {
+    ($name:ident, $create_method:ident, $destroy_method:ident,
+    [ $( $value:expr ),+ ], $expected:expr) => {
+        #[test]
+        fn $name() {
+            let trans = [$(
+                I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![$value])
+            ),*];
+            let mut dev = $create_method(&trans);
+            let samples = trans.len() as u8;
+            let result = dev
+                .oscillator_running_debounced(&mut MockNoop::new(), samples)
+                .unwrap();
+            assert_eq!($expected, result);
+            $destroy_method(dev);
+        }
+    };
+}
+
+for_all_ics!(
+    osc_running_single_sample,
+    debounced_test,
+    [BitFlags::OSCRUN],
+    true
+);
+for_all_ics!(
+    osc_running_all_samples,
+    debounced_test,
+    [
+        BitFlags::OSCRUN | 1,
+        BitFlags::OSCRUN | 1,
+        BitFlags::OSCRUN | 1
+    ],
+    true
+);
+for_all_ics!(
+    osc_flapping,
+    debounced_test,
+    [BitFlags::OSCRUN, 0, BitFlags::OSCRUN, BitFlags::OSCRUN],
+    false
+);
+for_all_ics!(osc_stopped, debounced_test, [0, 0], false);
+
+macro_rules! debounced_invalid_test {
+    ($name:ident, $create_method:ident, $destroy_method:ident, $samples:expr) => {
+        #[test]
+        fn $name() {
+            let mut dev = $create_method(&[]);
+            assert_invalid_input_data!(
+                dev.oscillator_running_debounced(&mut MockNoop::new(), $samples)
+            );
+            $destroy_method(dev);
+        }
+    };
+}
+
+for_all_ics!(osc_no_samples, debounced_invalid_test, 0);
+
+macro_rules! mfp_level_test {
+    ($name:ident, $control:expr, $expected:expr) => {
+        for_all_ics!(
+            $name,
+            get_test,
+            get_mfp_level_if_output,
+            [I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::CONTROL],
+                vec![$control]
+            )],
+            $expected
+        );
+    };
+}
+
+mfp_level_test!(mfp_gpo_high, BitFlags::OUT | 0b11, Some(true));
+mfp_level_test!(mfp_gpo_low, 0, Some(false));
+mfp_level_test!(mfp_square_wave, BitFlags::OUT | BitFlags::SQWEN, None);
+mfp_level_test!(mfp_alarm0, BitFlags::ALM0EN, None);
+mfp_level_test!(mfp_alarm1, BitFlags::OUT | BitFlags::ALM1EN, None);
+
+for_all_ics!(
+    ensure_running_when_running,
+    get_test,
+    ensure_running,
+    [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![BitFlags::ST | 0x12]),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::WEEKDAY],
+            vec![BitFlags::OSCRUN | 1]
+        )
+    ],
+    false,
+    (&mut MockNoop::new())
+);
+
+for_all_ics!(
+    ensure_running_restarts_stopped_clock,
+    get_test,
+    ensure_running,
+    [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![0x12]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![0x12]),
+        I2cTrans::write(DEV_ADDR, vec![Register::SECONDS, BitFlags::ST | 0x12]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::WEEKDAY], vec![1]),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::WEEKDAY],
+            vec![BitFlags::OSCRUN | 1]
+        )
+    ],
+    true,
+    (&mut MockNoop::new())
+);
+
+macro_rules! ensure_running_fails_test {
+    ($name:ident, $create_method:ident, $destroy_method:ident, $seconds:expr) => {
+        #[test]
+        fn $name() {
+            let mut trans = vec![I2cTrans::write_read(
+                DEV_ADDR,
+                vec![Register::SECONDS],
+                vec![$seconds],
+            )];
+            for _ in 0..100 {
+                trans.push(I2cTrans::write_read(
+                    DEV_ADDR,
+                    vec![Register::WEEKDAY],
+                    vec![1],
+                ));
+            }
+            let mut dev = $create_method(&trans);
+            match dev.ensure_running(&mut MockNoop::new()) {
+                Err(Error::OscillatorFailure) => (),
+                _ => panic!("OscillatorFailure error not returned."),
+            }
+            $destroy_method(dev);
+        }
+    };
+}
+
+for_all_ics!(
+    ensure_running_hardware_fault,
+    ensure_running_fails_test,
+    (BitFlags::ST | 0x12)
+);
+
+#[test]
+fn ensure_running_keeps_clock_running_on_later_writes() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![BitFlags::ST | 0x12]),
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::WEEKDAY],
+            vec![BitFlags::OSCRUN | 1],
+        ),
+        I2cTrans::write(DEV_ADDR, vec![Register::SECONDS, BitFlags::ST | 0x30]),
+    ];
+    let mut dev = new_mcp7940n(&trans);
+    assert!(!dev.ensure_running(&mut MockNoop::new()).unwrap());
+    dev.set_seconds(30).unwrap();
+    destroy_mcp7940n(dev);
+}
+
+for_battery_ics!(
+    has_power_failed,
+    get_test,
+    has_power_failed,
+    [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::WEEKDAY],
+        vec![BitFlags::PWRFAIL | 2]
+    )],
+    true
+);
+
+for_battery_ics!(
+    has_not_power_failed,
+    get_test,
+    has_power_failed,
+    [I2cTrans::write_read(
+        DEV_ADDR,
+        vec![Register::WEEKDAY],
+        vec![BitFlags::OSCRUN | BitFlags::VBATEN | 7]
+    )],
+    false
+);
+
+// The weekday, VBATEN and OSCRUN bits are kept.
+for_battery_ics!(
+    can_clear_power_failed,
+    call_test,
+    clear_power_failed,
+    [
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::WEEKDAY],
+            vec![BitFlags::OSCRUN | BitFlags::PWRFAIL | BitFlags::VBATEN | 5]
+        ),
+        I2cTrans::write(
+            DEV_ADDR,
+            vec![Register::WEEKDAY, BitFlags::OSCRUN | BitFlags::VBATEN | 5]
+        )
+    ]
+);
+
+#[test]
+fn mcp7940m_has_no_power_failed_flag() {
+    let mut dev = new_mcp7940m(&[]);
+    assert_feature_not_available!(dev.has_power_failed());
+    assert_feature_not_available!(dev.clear_power_failed());
+    assert_feature_not_available!(dev.time_survived_power_cycle());
+    destroy_mcp7940m(dev);
+}
+
+mod time_survived_power_cycle {
+    use super::*;
+
+    macro_rules! survived_test {
+        ($name:ident, $weekday:expr, $expected:expr) => {
+            for_battery_ics!(
+                $name,
+                get_test,
+                time_survived_power_cycle,
+                [I2cTrans::write_read(
+                    DEV_ADDR,
+                    vec![Register::WEEKDAY],
+                    vec![$weekday]
+                )],
+                $expected
+            );
+        };
+    }
+
+    survived_test!(
+        survived,
+        BitFlags::OSCRUN | BitFlags::PWRFAIL | BitFlags::VBATEN | 5,
+        true
+    );
+    survived_test!(
+        no_power_failure,
+        BitFlags::OSCRUN | BitFlags::VBATEN | 5,
+        false
+    );
+    survived_test!(
+        without_battery_backup,
+        BitFlags::OSCRUN | BitFlags::PWRFAIL | 5,
+        false
+    );
+    survived_test!(
+        oscillator_stopped,
+        BitFlags::PWRFAIL | BitFlags::VBATEN | 5,
+        false
+    );
+}
+
+mod tick_rate {
+    use super::*;
+
+    fn read_seconds(seconds: u8) -> I2cTrans {
+        I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::SECONDS],
+            vec![BitFlags::ST | seconds],
+        )
+    }
+
+    for_all_ics!(
+        ticking,
+        get_test,
+        verify_seconds_tick_rate,
+        [
+            read_seconds(0x10),
+            read_seconds(0x10),
+            read_seconds(0x11),
+            read_seconds(0x13)
+        ],
+        true,
+        (&mut MockNoop::new())
+    );
+
+    for_all_ics!(
+        ticking_across_minute,
+        get_test,
+        verify_seconds_tick_rate,
+        [read_seconds(0x58), read_seconds(0x59), read_seconds(0x01)],
+        true,
+        (&mut MockNoop::new())
+    );
+
+    for_all_ics!(
+        ticking_slow,
+        get_test,
+        verify_seconds_tick_rate,
+        [read_seconds(0x10), read_seconds(0x11), read_seconds(0x12)],
+        false,
+        (&mut MockNoop::new())
+    );
+
+    for_all_ics!(
+        ticking_fast,
+        get_test,
+        verify_seconds_tick_rate,
+        [read_seconds(0x10), read_seconds(0x11), read_seconds(0x14)],
+        false,
+        (&mut MockNoop::new())
+    );
+
+    macro_rules! stopped_test {
+        ($name:ident, $create_method:ident, $destroy_method:ident, $seconds:expr) => {
+            #[test]
+            fn $name() {
+                let trans: Vec<_> = (0..111).map(|_| read_seconds($seconds)).collect();
+                let mut dev = $create_method(&trans);
+                assert!(!dev.verify_seconds_tick_rate(&mut MockNoop::new()).unwrap());
+                $destroy_method(dev);
+            }
+        };
+    }
+
+    for_all_ics!(stopped, stopped_test, 0x25);
+}
+
+mod status_line {
+    use super::*;
+
+    macro_rules! status_line_test {
+        ($name:ident, $create_method:ident, $destroy_method:ident, $registers:expr,
+        $expected:expr) => {
+            #[test]
+            fn $name() {
+                let trans = [I2cTrans::write_read(
+                    DEV_ADDR,
+                    vec![Register::SECONDS],
+                    $registers.to_vec(),
+                )];
+                let mut dev = $create_method(&trans);
+                let mut buf = [0; 32];
+                let len = dev.status_line(&mut buf).unwrap();
+                assert_eq!(&$expected[..], &buf[..len]);
+                $destroy_method(dev);
+            }
+        };
+    }
+
+    for_all_ics!(
+        running_12h,
+        status_line_test,
+        [
+            BitFlags::ST | 0x12,
+            0x34,
+            BitFlags::H24_H12 | 0x08,
+            BitFlags::OSCRUN | BitFlags::VBATEN | 3
+        ],
+        b"RUN osc=1 pf=0 vbat=1 12h"
+    );
+
+    for_all_ics!(
+        stopped_24h,
+        status_line_test,
+        [0, 0, 0x23, BitFlags::PWRFAIL | 1],
+        b"STOP osc=0 pf=1 vbat=0 24h"
+    );
+
+    #[test]
+    fn buffer_too_small() {
+        let trans = [I2cTrans::write_read(
+            DEV_ADDR,
+            vec![Register::SECONDS],
+            vec![BitFlags::ST, 0, 0, 0],
+        )];
+        let mut dev = new_mcp7940n(&trans);
+        let mut buf = [0; 24];
+        assert_invalid_input_data!(dev.status_line(&mut buf));
+        destroy_mcp7940n(dev);
+    }
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "std")]
+extern crate embedded_hal;
+extern crate embedded_hal_mock as hal;
+use hal::i2c::Transaction as I2cTrans;
+mod common;
+use common::{destroy_mcp7940n, new_mcp7940n, BitFlags, Register, DEVICE_ADDRESS as DEV_ADDR};
+extern crate mcp794xx;
+use mcp794xx::{Mcp794xx, Rtcc};
+
+#[test]
+fn records_timing_of_each_transaction() {
+    let trans = [
+        I2cTrans::write_read(DEV_ADDR, vec![Register::SECONDS], vec![0]),
+        I2cTrans::write(DEV_ADDR, vec![Register::SECONDS, BitFlags::ST]),
+        I2cTrans::write_read(DEV_ADDR, vec![Register::MINUTES], vec![0b0001_0011]),
+    ];
+    let mut dev = new_mcp7940n(&trans).into_timed();
+    dev.enable().unwrap();
+    assert_eq!(13, dev.get_minutes().unwrap());
+    assert_eq!(3, dev.timings().len());
+    dev.clear_timings();
+    assert!(dev.timings().is_empty());
+    destroy_mcp7940n(dev.into_untimed());
+}
+
+struct SlowI2c;
+
+impl embedded_hal::blocking::i2c::WriteRead for SlowI2c {
+    type Error = ();
+
+    fn write_read(&mut self, _address: u8, _bytes: &[u8], buffer: &mut [u8]) -> Result<(), ()> {
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        for byte in buffer.iter_mut() {
+            *byte = 0;
+        }
+        Ok(())
+    }
+}
+
+impl embedded_hal::blocking::i2c::Write for SlowI2c {
+    type Error = ();
+
+    fn write(&mut self, _address: u8, _bytes: &[u8]) -> Result<(), ()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn can_measure_bus_latency() {
+    let mut dev = Mcp794xx::new_mcp7940n(SlowI2c);
+    let latency = dev.measure_bus_latency().unwrap();
+    assert!(latency >= std::time::Duration::from_millis(1));
+}
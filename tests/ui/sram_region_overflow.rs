@@ -0,0 +1,12 @@
+extern crate embedded_hal_mock as hal;
+extern crate mcp794xx;
+use hal::i2c::Mock as I2cMock;
+use mcp794xx::{Mcp794xx, SramRegion};
+
+type Log = SramRegion<60, 8>;
+
+fn main() {
+    let mut rtc = Mcp794xx::new_mcp7940n(I2cMock::new(&[]));
+    let mut buf = [0; Log::SIZE];
+    Log::read(&mut rtc, &mut buf).unwrap();
+}
@@ -0,0 +1,9 @@
+extern crate embedded_hal_mock as hal;
+extern crate mcp794xx;
+use hal::i2c::Mock as I2cMock;
+use mcp794xx::Mcp794xx;
+
+fn main() {
+    let mut rtc = Mcp794xx::new_mcp7940m(I2cMock::new(&[]));
+    rtc.write_eui(&[0; 6]).unwrap();
+}